@@ -527,7 +527,10 @@ where
 		.await
 		{
 			Ok(true) => {},
-			Ok(false) => return Ok(ValidationResult::Invalid(InvalidCandidate::InvalidOutputs)),
+			Ok(false) => {
+				log_invalid_outputs(sender, &candidate_receipt, outputs).await;
+				return Ok(ValidationResult::Invalid(InvalidCandidate::InvalidOutputs))
+			},
 			Err(RuntimeRequestFailed) =>
 				return Err(ValidationFailed("Check Validation Outputs: Bad request".into())),
 		}
@@ -536,6 +539,43 @@ where
 	validation_result
 }
 
+/// Logs why the runtime rejected the outputs of a candidate, as far as the runtime can tell.
+async fn log_invalid_outputs<Sender>(
+	sender: &mut Sender,
+	candidate_receipt: &CandidateReceipt,
+	outputs: &CandidateCommitments,
+) where
+	Sender: SubsystemSender<RuntimeApiMessage>,
+{
+	let para_id = candidate_receipt.descriptor.para_id;
+	let candidate_hash = candidate_receipt.hash();
+
+	let (tx, rx) = oneshot::channel();
+	match runtime_api_request(
+		sender,
+		candidate_receipt.descriptor.relay_parent,
+		RuntimeApiRequest::CheckProcessedDownwardMessages(
+			para_id,
+			outputs.processed_downward_messages,
+			tx,
+		),
+		rx,
+	)
+	.await
+	{
+		Ok(Err(e)) => gum::info!(
+			target: LOG_TARGET,
+			?para_id,
+			?candidate_hash,
+			err = ?e,
+			"Invalid candidate (processed downward messages)",
+		),
+		// The runtime doesn't support the check or the downward messages aren't the reason.
+		Ok(Ok(())) | Err(RuntimeRequestFailed) =>
+			gum::info!(target: LOG_TARGET, ?para_id, ?candidate_hash, "Invalid candidate (outputs)"),
+	}
+}
+
 async fn validate_candidate_exhaustive<Sender>(
 	sender: &mut Sender,
 	mut validation_backend: impl ValidationBackend + Send,
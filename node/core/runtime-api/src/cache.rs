@@ -20,11 +20,12 @@ use lru::LruCache;
 use sp_consensus_babe::Epoch;
 
 use polkadot_primitives::{
-	AuthorityDiscoveryId, BlockNumber, CandidateCommitments, CandidateEvent, CandidateHash,
-	CommittedCandidateReceipt, CoreState, DisputeState, ExecutorParams, GroupRotationInfo, Hash,
-	Id as ParaId, InboundDownwardMessage, InboundHrmpMessage, OccupiedCoreAssumption,
-	PersistedValidationData, PvfCheckStatement, ScrapedOnChainVotes, SessionIndex, SessionInfo,
-	ValidationCode, ValidationCodeHash, ValidatorId, ValidatorIndex, ValidatorSignature,
+	vstaging, AuthorityDiscoveryId, BlockNumber, CandidateCommitments, CandidateEvent,
+	CandidateHash, CommittedCandidateReceipt, CoreState, DisputeState, ExecutorParams,
+	GroupRotationInfo, Hash, Id as ParaId, InboundDownwardMessage, InboundHrmpMessage,
+	OccupiedCoreAssumption, PersistedValidationData, PvfCheckStatement, ScrapedOnChainVotes,
+	SessionIndex, SessionInfo, ValidationCode, ValidationCodeHash, ValidatorId, ValidatorIndex,
+	ValidatorSignature,
 };
 
 /// For consistency we have the same capacity for all caches. We use 128 as we'll only need that
@@ -63,6 +64,12 @@ pub(crate) struct RequestResultCache {
 		LruCache<(Hash, ParaId, OccupiedCoreAssumption), Option<ValidationCodeHash>>,
 	version: LruCache<Hash, u32>,
 	disputes: LruCache<Hash, Vec<(SessionIndex, CandidateHash, DisputeState<BlockNumber>)>>,
+	dmq_mqc_head: LruCache<(Hash, ParaId), Hash>,
+	dmq_stats: LruCache<(Hash, ParaId), vstaging::DmqStats>,
+	dmq_remaining_capacity: LruCache<(Hash, ParaId), vstaging::DmqCapacity>,
+	dmq_last_pruned_mqc_head: LruCache<(Hash, ParaId), Option<Hash>>,
+	check_processed_downward_messages:
+		LruCache<(Hash, ParaId, u32), Result<(), vstaging::ProcessedDownwardMessagesAcceptanceErr>>,
 }
 
 impl Default for RequestResultCache {
@@ -90,6 +97,11 @@ impl Default for RequestResultCache {
 			validation_code_hash: LruCache::new(DEFAULT_CACHE_CAP),
 			version: LruCache::new(DEFAULT_CACHE_CAP),
 			disputes: LruCache::new(DEFAULT_CACHE_CAP),
+			dmq_mqc_head: LruCache::new(DEFAULT_CACHE_CAP),
+			dmq_stats: LruCache::new(DEFAULT_CACHE_CAP),
+			dmq_remaining_capacity: LruCache::new(DEFAULT_CACHE_CAP),
+			dmq_last_pruned_mqc_head: LruCache::new(DEFAULT_CACHE_CAP),
+			check_processed_downward_messages: LruCache::new(DEFAULT_CACHE_CAP),
 		}
 	}
 }
@@ -385,6 +397,67 @@ impl RequestResultCache {
 	) {
 		self.disputes.put(relay_parent, value);
 	}
+
+	pub(crate) fn dmq_mqc_head(&mut self, key: (Hash, ParaId)) -> Option<&Hash> {
+		self.dmq_mqc_head.get(&key)
+	}
+
+	pub(crate) fn cache_dmq_mqc_head(&mut self, key: (Hash, ParaId), value: Hash) {
+		self.dmq_mqc_head.put(key, value);
+	}
+
+	pub(crate) fn dmq_stats(&mut self, key: (Hash, ParaId)) -> Option<&vstaging::DmqStats> {
+		self.dmq_stats.get(&key)
+	}
+
+	pub(crate) fn cache_dmq_stats(&mut self, key: (Hash, ParaId), value: vstaging::DmqStats) {
+		self.dmq_stats.put(key, value);
+	}
+
+	pub(crate) fn dmq_remaining_capacity(
+		&mut self,
+		key: (Hash, ParaId),
+	) -> Option<&vstaging::DmqCapacity> {
+		self.dmq_remaining_capacity.get(&key)
+	}
+
+	pub(crate) fn cache_dmq_remaining_capacity(
+		&mut self,
+		key: (Hash, ParaId),
+		value: vstaging::DmqCapacity,
+	) {
+		self.dmq_remaining_capacity.put(key, value);
+	}
+
+	pub(crate) fn dmq_last_pruned_mqc_head(
+		&mut self,
+		key: (Hash, ParaId),
+	) -> Option<&Option<Hash>> {
+		self.dmq_last_pruned_mqc_head.get(&key)
+	}
+
+	pub(crate) fn cache_dmq_last_pruned_mqc_head(
+		&mut self,
+		key: (Hash, ParaId),
+		value: Option<Hash>,
+	) {
+		self.dmq_last_pruned_mqc_head.put(key, value);
+	}
+
+	pub(crate) fn check_processed_downward_messages(
+		&mut self,
+		key: (Hash, ParaId, u32),
+	) -> Option<&Result<(), vstaging::ProcessedDownwardMessagesAcceptanceErr>> {
+		self.check_processed_downward_messages.get(&key)
+	}
+
+	pub(crate) fn cache_check_processed_downward_messages(
+		&mut self,
+		key: (Hash, ParaId, u32),
+		value: Result<(), vstaging::ProcessedDownwardMessagesAcceptanceErr>,
+	) {
+		self.check_processed_downward_messages.put(key, value);
+	}
 }
 
 pub(crate) enum RequestResult {
@@ -422,4 +495,14 @@ pub(crate) enum RequestResult {
 	ValidationCodeHash(Hash, ParaId, OccupiedCoreAssumption, Option<ValidationCodeHash>),
 	Version(Hash, u32),
 	Disputes(Hash, Vec<(SessionIndex, CandidateHash, DisputeState<BlockNumber>)>),
+	DmqMqcHead(Hash, ParaId, Hash),
+	DmqStats(Hash, ParaId, vstaging::DmqStats),
+	DmqRemainingCapacity(Hash, ParaId, vstaging::DmqCapacity),
+	DmqLastPrunedMqcHead(Hash, ParaId, Option<Hash>),
+	CheckProcessedDownwardMessages(
+		Hash,
+		ParaId,
+		u32,
+		Result<(), vstaging::ProcessedDownwardMessagesAcceptanceErr>,
+	),
 }
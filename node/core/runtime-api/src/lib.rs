@@ -157,6 +157,19 @@ where
 				self.requests_cache.cache_version(relay_parent, version),
 			Disputes(relay_parent, disputes) =>
 				self.requests_cache.cache_disputes(relay_parent, disputes),
+			DmqMqcHead(relay_parent, para_id, head) =>
+				self.requests_cache.cache_dmq_mqc_head((relay_parent, para_id), head),
+			DmqStats(relay_parent, para_id, stats) =>
+				self.requests_cache.cache_dmq_stats((relay_parent, para_id), stats),
+			DmqRemainingCapacity(relay_parent, para_id, capacity) => self
+				.requests_cache
+				.cache_dmq_remaining_capacity((relay_parent, para_id), capacity),
+			DmqLastPrunedMqcHead(relay_parent, para_id, head) => self
+				.requests_cache
+				.cache_dmq_last_pruned_mqc_head((relay_parent, para_id), head),
+			CheckProcessedDownwardMessages(relay_parent, para_id, processed, res) => self
+				.requests_cache
+				.cache_check_processed_downward_messages((relay_parent, para_id, processed), res),
 		}
 	}
 
@@ -271,6 +284,18 @@ where
 					.map(|sender| Request::ValidationCodeHash(para, assumption, sender)),
 			Request::Disputes(sender) =>
 				query!(disputes(), sender).map(|sender| Request::Disputes(sender)),
+			Request::DmqMqcHead(id, sender) =>
+				query!(dmq_mqc_head(id), sender).map(|sender| Request::DmqMqcHead(id, sender)),
+			Request::DmqStats(id, sender) =>
+				query!(dmq_stats(id), sender).map(|sender| Request::DmqStats(id, sender)),
+			Request::DmqRemainingCapacity(id, sender) => query!(dmq_remaining_capacity(id), sender)
+				.map(|sender| Request::DmqRemainingCapacity(id, sender)),
+			Request::DmqLastPrunedMqcHead(id, sender) =>
+				query!(dmq_last_pruned_mqc_head(id), sender)
+					.map(|sender| Request::DmqLastPrunedMqcHead(id, sender)),
+			Request::CheckProcessedDownwardMessages(id, processed, sender) =>
+				query!(check_processed_downward_messages(id, processed), sender)
+					.map(|sender| Request::CheckProcessedDownwardMessages(id, processed, sender)),
 		}
 	}
 
@@ -490,5 +515,31 @@ where
 			query!(ValidationCodeHash, validation_code_hash(para, assumption), ver = 2, sender),
 		Request::Disputes(sender) =>
 			query!(Disputes, disputes(), ver = Request::DISPUTES_RUNTIME_REQUIREMENT, sender),
+		Request::DmqMqcHead(id, sender) => query!(
+			DmqMqcHead,
+			dmq_mqc_head(id),
+			ver = Request::DMQ_MQC_HEAD_RUNTIME_REQUIREMENT,
+			sender
+		),
+		Request::DmqStats(id, sender) =>
+			query!(DmqStats, dmq_stats(id), ver = Request::DMQ_STATS_RUNTIME_REQUIREMENT, sender),
+		Request::DmqRemainingCapacity(id, sender) => query!(
+			DmqRemainingCapacity,
+			dmq_remaining_capacity(id),
+			ver = Request::DMQ_REMAINING_CAPACITY_RUNTIME_REQUIREMENT,
+			sender
+		),
+		Request::DmqLastPrunedMqcHead(id, sender) => query!(
+			DmqLastPrunedMqcHead,
+			dmq_last_pruned_mqc_head(id),
+			ver = Request::DMQ_LAST_PRUNED_MQC_HEAD_RUNTIME_REQUIREMENT,
+			sender
+		),
+		Request::CheckProcessedDownwardMessages(id, processed, sender) => query!(
+			CheckProcessedDownwardMessages,
+			check_processed_downward_messages(id, processed),
+			ver = Request::CHECK_PROCESSED_DOWNWARD_MESSAGES_RUNTIME_REQUIREMENT,
+			sender
+		),
 	}
 }
@@ -606,6 +606,22 @@ pub enum RuntimeApiRequest {
 	),
 	/// Returns all on-chain disputes at given block number. Available in `v3`.
 	Disputes(RuntimeApiSender<Vec<(SessionIndex, CandidateHash, DisputeState<BlockNumber>)>>),
+	/// Get the MQC head of the downward message queue for a para. Available in `v5`.
+	DmqMqcHead(ParaId, RuntimeApiSender<Hash>),
+	/// Get a summary of the downward message queue for a para. Available in `v5`.
+	DmqStats(ParaId, RuntimeApiSender<vstaging::DmqStats>),
+	/// Get how much more can be sent to the downward message queue of a para. Available in `v5`.
+	DmqRemainingCapacity(ParaId, RuntimeApiSender<vstaging::DmqCapacity>),
+	/// Get the MQC head after the last message that was removed from the downward message queue
+	/// of a para. Available in `v5`.
+	DmqLastPrunedMqcHead(ParaId, RuntimeApiSender<Option<Hash>>),
+	/// Check the number of downward messages processed by a candidate of a para, returning why
+	/// the candidate would be rejected if so. Available in `v5`.
+	CheckProcessedDownwardMessages(
+		ParaId,
+		u32,
+		RuntimeApiSender<Result<(), vstaging::ProcessedDownwardMessagesAcceptanceErr>>,
+	),
 }
 
 impl RuntimeApiRequest {
@@ -616,6 +632,21 @@ impl RuntimeApiRequest {
 
 	/// `ExecutorParams`
 	pub const EXECUTOR_PARAMS_RUNTIME_REQUIREMENT: u32 = 4;
+
+	/// `DmqMqcHead`
+	pub const DMQ_MQC_HEAD_RUNTIME_REQUIREMENT: u32 = 5;
+
+	/// `DmqStats`
+	pub const DMQ_STATS_RUNTIME_REQUIREMENT: u32 = 5;
+
+	/// `DmqRemainingCapacity`
+	pub const DMQ_REMAINING_CAPACITY_RUNTIME_REQUIREMENT: u32 = 5;
+
+	/// `DmqLastPrunedMqcHead`
+	pub const DMQ_LAST_PRUNED_MQC_HEAD_RUNTIME_REQUIREMENT: u32 = 5;
+
+	/// `CheckProcessedDownwardMessages`
+	pub const CHECK_PROCESSED_DOWNWARD_MESSAGES_RUNTIME_REQUIREMENT: u32 = 5;
 }
 
 /// A message to the Runtime API subsystem.
@@ -189,6 +189,42 @@ pub trait RuntimeApiSubsystemClient {
 		session_index: SessionIndex,
 	) -> Result<Option<ExecutorParams>, ApiError>;
 
+	/***** Staging *****/
+
+	/// Get the MQC head of the downward message queue for a para.
+	/// This is a staging method! Do not use on production runtimes!
+	async fn dmq_mqc_head(&self, at: Hash, recipient: Id) -> Result<Hash, ApiError>;
+
+	/// Get a summary of the downward message queue for a para.
+	/// This is a staging method! Do not use on production runtimes!
+	async fn dmq_stats(&self, at: Hash, recipient: Id) -> Result<vstaging::DmqStats, ApiError>;
+
+	/// Get how much more can be sent to the downward message queue of a para.
+	/// This is a staging method! Do not use on production runtimes!
+	async fn dmq_remaining_capacity(
+		&self,
+		at: Hash,
+		recipient: Id,
+	) -> Result<vstaging::DmqCapacity, ApiError>;
+
+	/// Get the MQC head after the last message that was removed from the downward message queue
+	/// of a para.
+	/// This is a staging method! Do not use on production runtimes!
+	async fn dmq_last_pruned_mqc_head(
+		&self,
+		at: Hash,
+		recipient: Id,
+	) -> Result<Option<Hash>, ApiError>;
+
+	/// Check the number of downward messages processed by a candidate of a para.
+	/// This is a staging method! Do not use on production runtimes!
+	async fn check_processed_downward_messages(
+		&self,
+		at: Hash,
+		recipient: Id,
+		processed_downward_messages: u32,
+	) -> Result<Result<(), vstaging::ProcessedDownwardMessagesAcceptanceErr>, ApiError>;
+
 	// === BABE API ===
 
 	/// Returns information regarding the current epoch.
@@ -323,6 +359,43 @@ where
 		self.runtime_api().session_executor_params(at, session_index)
 	}
 
+	async fn dmq_mqc_head(&self, at: Hash, recipient: Id) -> Result<Hash, ApiError> {
+		self.runtime_api().dmq_mqc_head(at, recipient)
+	}
+
+	async fn dmq_stats(&self, at: Hash, recipient: Id) -> Result<vstaging::DmqStats, ApiError> {
+		self.runtime_api().dmq_stats(at, recipient)
+	}
+
+	async fn dmq_remaining_capacity(
+		&self,
+		at: Hash,
+		recipient: Id,
+	) -> Result<vstaging::DmqCapacity, ApiError> {
+		self.runtime_api().dmq_remaining_capacity(at, recipient)
+	}
+
+	async fn dmq_last_pruned_mqc_head(
+		&self,
+		at: Hash,
+		recipient: Id,
+	) -> Result<Option<Hash>, ApiError> {
+		self.runtime_api().dmq_last_pruned_mqc_head(at, recipient)
+	}
+
+	async fn check_processed_downward_messages(
+		&self,
+		at: Hash,
+		recipient: Id,
+		processed_downward_messages: u32,
+	) -> Result<Result<(), vstaging::ProcessedDownwardMessagesAcceptanceErr>, ApiError> {
+		self.runtime_api().check_processed_downward_messages(
+			at,
+			recipient,
+			processed_downward_messages,
+		)
+	}
+
 	async fn session_info(
 		&self,
 		at: Hash,
@@ -214,6 +214,11 @@ specialize_requests! {
 		-> Option<ValidationCodeHash>; ValidationCodeHash;
 	fn request_on_chain_votes() -> Option<ScrapedOnChainVotes>; FetchOnChainVotes;
 	fn request_session_executor_params(session_index: SessionIndex) -> Option<ExecutorParams>; SessionExecutorParams;
+	fn request_dmq_mqc_head(para_id: ParaId) -> Hash; DmqMqcHead;
+	fn request_dmq_stats(para_id: ParaId) -> polkadot_primitives::vstaging::DmqStats; DmqStats;
+	fn request_dmq_remaining_capacity(para_id: ParaId) -> polkadot_primitives::vstaging::DmqCapacity; DmqRemainingCapacity;
+	fn request_dmq_last_pruned_mqc_head(para_id: ParaId) -> Option<Hash>; DmqLastPrunedMqcHead;
+	fn request_check_processed_downward_messages(para_id: ParaId, processed_downward_messages: u32) -> Result<(), polkadot_primitives::vstaging::ProcessedDownwardMessagesAcceptanceErr>; CheckProcessedDownwardMessages;
 }
 
 /// Requests executor parameters from the runtime effective at given relay-parent. First obtains
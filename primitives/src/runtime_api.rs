@@ -111,10 +111,10 @@
 //! from the stable primitives.
 
 use crate::{
-	BlockNumber, CandidateCommitments, CandidateEvent, CandidateHash, CommittedCandidateReceipt,
-	CoreState, DisputeState, ExecutorParams, GroupRotationInfo, OccupiedCoreAssumption,
-	PersistedValidationData, PvfCheckStatement, ScrapedOnChainVotes, SessionIndex, SessionInfo,
-	ValidatorId, ValidatorIndex, ValidatorSignature,
+	vstaging, BlockNumber, CandidateCommitments, CandidateEvent, CandidateHash,
+	CommittedCandidateReceipt, CoreState, DisputeState, ExecutorParams, GroupRotationInfo,
+	OccupiedCoreAssumption, PersistedValidationData, PvfCheckStatement, ScrapedOnChainVotes,
+	SessionIndex, SessionInfo, ValidatorId, ValidatorIndex, ValidatorSignature,
 };
 use parity_scale_codec::{Decode, Encode};
 use polkadot_core_primitives as pcp;
@@ -218,5 +218,37 @@ sp_api::decl_runtime_apis! {
 
 		/// Returns execution parameters for the session.
 		fn session_executor_params(session_index: SessionIndex) -> Option<ExecutorParams>;
+
+		/***** Staging *****/
+
+		/// Get the MQC head of the downward message queue for a para.
+		///
+		/// Returns the zero hash if the para doesn't have an associated downward message queue.
+		#[api_version(5)]
+		fn dmq_mqc_head(recipient: ppp::Id) -> pcp::v2::Hash;
+
+		/// Get a summary of the downward message queue for a para.
+		#[api_version(5)]
+		fn dmq_stats(recipient: ppp::Id) -> vstaging::DmqStats<N>;
+
+		/// Get how much more can be sent to the downward message queue of a para.
+		#[api_version(5)]
+		fn dmq_remaining_capacity(recipient: ppp::Id) -> vstaging::DmqCapacity;
+
+		/// Get the MQC head after the last message that was removed from the downward message
+		/// queue of a para.
+		///
+		/// Returns `None` if no message was removed since this started being tracked.
+		#[api_version(5)]
+		fn dmq_last_pruned_mqc_head(recipient: ppp::Id) -> Option<pcp::v2::Hash>;
+
+		/// Check the number of downward messages processed by a candidate of a para.
+		///
+		/// Returns why a candidate that processed that many messages would be rejected, if so.
+		#[api_version(5)]
+		fn check_processed_downward_messages(
+			recipient: ppp::Id,
+			processed_downward_messages: u32,
+		) -> Result<(), vstaging::ProcessedDownwardMessagesAcceptanceErr>;
 	}
 }
@@ -18,7 +18,7 @@
 
 // Put any primitives used by staging APIs functions here
 pub use crate::v4::*;
-use sp_std::prelude::*;
+use sp_std::{fmt, prelude::*};
 
 use parity_scale_codec::{Decode, Encode};
 use primitives::RuntimeDebug;
@@ -40,3 +40,71 @@ pub struct AsyncBackingParams {
 	/// When async backing is disabled, the only valid value is 0.
 	pub allowed_ancestry_len: u32,
 }
+
+/// A summary of the downward message queue of a para.
+#[derive(RuntimeDebug, Copy, Clone, Default, PartialEq, Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+pub struct DmqStats<N = BlockNumber> {
+	/// The number of pending downward messages.
+	pub message_count: u32,
+	/// The total size in bytes of the pending downward messages.
+	pub total_bytes: u64,
+	/// The block number at which the oldest pending downward message was sent, if any.
+	pub oldest_sent_at: Option<N>,
+}
+
+/// How much more can be sent to the downward message queue of a para.
+#[derive(RuntimeDebug, Copy, Clone, Default, PartialEq, Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(serde::Serialize, serde::Deserialize))]
+pub struct DmqCapacity {
+	/// The number of messages that can still be queued.
+	pub messages_free: u64,
+	/// The number of bytes that can still be queued.
+	pub bytes_free: u64,
+}
+
+/// An error that indicates that the number of downward messages processed by a candidate didn't
+/// pass the acceptance checks.
+#[derive(Encode, Decode, TypeInfo, Clone, PartialEq, Eq)]
+pub enum ProcessedDownwardMessagesAcceptanceErr {
+	/// If there are pending messages then `processed_downward_messages` should be at least 1,
+	AdvancementRule { para: Id },
+	/// If there are pending messages then `processed_downward_messages` should be at least the
+	/// configured minimum, or the number of pending messages if that is lower.
+	AdvancementRuleMinimum { para: Id, processed_downward_messages: u32, required: u32 },
+	/// `processed_downward_messages` should not be greater than the number of pending messages.
+	Underflow { para: Id, processed_downward_messages: u32, dmq_length: u32 },
+}
+
+impl fmt::Display for ProcessedDownwardMessagesAcceptanceErr {
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		use ProcessedDownwardMessagesAcceptanceErr::*;
+		match *self {
+			AdvancementRule { para } => write!(
+				fmt,
+				"DMQ of para {} is not empty, but processed_downward_messages is 0",
+				u32::from(para),
+			),
+			AdvancementRuleMinimum { para, processed_downward_messages, required } => write!(
+				fmt,
+				"processed_downward_messages = {} for para {}, but at least {} are required",
+				processed_downward_messages,
+				u32::from(para),
+				required,
+			),
+			Underflow { para, processed_downward_messages, dmq_length } => write!(
+				fmt,
+				"processed_downward_messages = {} for para {}, but dmq_length is only {}",
+				processed_downward_messages,
+				u32::from(para),
+				dmq_length,
+			),
+		}
+	}
+}
+
+impl fmt::Debug for ProcessedDownwardMessagesAcceptanceErr {
+	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+		fmt::Display::fmt(self, fmt)
+	}
+}
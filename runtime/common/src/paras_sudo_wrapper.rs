@@ -61,6 +61,14 @@ pub mod pallet {
 		CannotUpgrade,
 		/// Cannot downgrade parachain.
 		CannotDowngrade,
+		/// A DMP message couldn't be sent because it would make the downward message queue of the
+		/// para exceed the maximum size allowed.
+		QueueBytesExceeded,
+		/// A DMP message couldn't be sent because the downward message queue of the para already
+		/// holds the maximum number of pending messages.
+		QueueLimitReached,
+		/// A DMP message couldn't be sent because the para is being offboarded.
+		RecipientOffboarding,
 	}
 
 	#[pallet::hooks]
@@ -133,7 +141,12 @@ pub mod pallet {
 		/// The given parachain should exist and the payload should not exceed the preconfigured size
 		/// `config.max_downward_message_size`.
 		#[pallet::call_index(4)]
-		#[pallet::weight((1_000, DispatchClass::Operational))]
+		#[pallet::weight((
+			<<T as dmp::Config>::WeightInfo as dmp::WeightInfo>::queue_downward_message(
+				xcm.encoded_size() as u32,
+			),
+			DispatchClass::Operational,
+		))]
 		pub fn sudo_queue_downward_xcm(
 			origin: OriginFor<T>,
 			id: ParaId,
@@ -146,6 +159,12 @@ pub mod pallet {
 			{
 				dmp::QueueDownwardMessageError::ExceedsMaxMessageSize =>
 					Error::<T>::ExceedsMaxMessageSize.into(),
+				dmp::QueueDownwardMessageError::QueueBytesExceeded =>
+					Error::<T>::QueueBytesExceeded.into(),
+				dmp::QueueDownwardMessageError::RecipientOffboarding =>
+					Error::<T>::RecipientOffboarding.into(),
+				dmp::QueueDownwardMessageError::QueueLimitReached =>
+					Error::<T>::QueueLimitReached.into(),
 			})
 		}
 
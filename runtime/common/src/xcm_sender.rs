@@ -92,7 +92,7 @@ impl<T: configuration::Config + dmp::Config, W: xcm::WrapVersion, P: PriceForPar
 		let para = id.into();
 		let price = P::price_for_parachain_delivery(para, &xcm);
 		let blob = W::wrap_version(&d, xcm).map_err(|()| DestinationUnsupported)?.encode();
-		<dmp::Pallet<T>>::can_queue_downward_message(&config, &para, &blob)
+		<dmp::Pallet<T>>::can_queue_downward_message(&config, &para, blob.len() as u32)
 			.map_err(Into::<SendError>::into)?;
 
 		Ok(((config, para, blob), price))
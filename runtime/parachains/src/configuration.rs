@@ -27,6 +27,7 @@ use primitives::{
 	vstaging::AsyncBackingParams, Balance, ExecutorParams, SessionIndex, MAX_CODE_SIZE,
 	MAX_HEAD_DATA_SIZE, MAX_POV_SIZE,
 };
+use sp_core::MAX_POSSIBLE_ALLOCATION;
 use sp_runtime::traits::Zero;
 use sp_std::prelude::*;
 
@@ -132,6 +133,23 @@ pub struct HostConfiguration<BlockNumber> {
 	/// decide to do with its PoV so this value in practice will be picked as a fraction of the PoV
 	/// size.
 	pub max_downward_message_size: u32,
+	/// Total size of messages in bytes allowed in a single downward message queue.
+	///
+	/// Messages that would make the queue exceed this size are rejected. This can't exceed
+	/// `MAX_POSSIBLE_ALLOCATION` since the whole queue has to be decoded at once.
+	pub max_downward_queue_bytes: u32,
+	/// The minimum number of downward messages a candidate has to process if its para has pending
+	/// downward messages.
+	///
+	/// If fewer messages are pending, all of them have to be processed. Must be non-zero.
+	pub dmp_advancement_rule_min_processed: u32,
+	/// The number of blocks after which pending downward messages expire and are dropped from the
+	/// queue. Zero disables expiry.
+	pub downward_message_ttl: BlockNumber,
+	/// Whether downward messages to paras that are being offboarded are rejected.
+	///
+	/// The queues of such paras are removed at the next session change anyway.
+	pub dmp_reject_offboarding_recipients: bool,
 	/// The amount of weight we wish to devote to the processing the dispatchable upward messages
 	/// stage.
 	///
@@ -278,6 +296,10 @@ impl<BlockNumber: Default + From<u32>> Default for HostConfiguration<BlockNumber
 			max_upward_queue_count: Default::default(),
 			max_upward_queue_size: Default::default(),
 			max_downward_message_size: Default::default(),
+			max_downward_queue_bytes: MAX_POSSIBLE_ALLOCATION,
+			dmp_advancement_rule_min_processed: 1,
+			downward_message_ttl: Default::default(),
+			dmp_reject_offboarding_recipients: false,
 			ump_service_total_weight: Default::default(),
 			max_upward_message_size: Default::default(),
 			max_upward_message_num_per_candidate: Default::default(),
@@ -342,6 +364,10 @@ pub enum InconsistentError<BlockNumber> {
 	MaxHrmpOutboundChannelsExceeded,
 	/// Maximum number of HRMP inbound channels exceeded.
 	MaxHrmpInboundChannelsExceeded,
+	/// `max_downward_queue_bytes` exceeds the hard limit of `MAX_POSSIBLE_ALLOCATION`.
+	MaxDownwardQueueBytesExceedHardLimit { max_downward_queue_bytes: u32 },
+	/// `dmp_advancement_rule_min_processed` is set to zero.
+	ZeroDmpAdvancementRuleMinProcessed,
 }
 
 impl<BlockNumber> HostConfiguration<BlockNumber>
@@ -431,6 +457,16 @@ where
 			return Err(MaxHrmpInboundChannelsExceeded)
 		}
 
+		if self.max_downward_queue_bytes > MAX_POSSIBLE_ALLOCATION {
+			return Err(MaxDownwardQueueBytesExceedHardLimit {
+				max_downward_queue_bytes: self.max_downward_queue_bytes,
+			})
+		}
+
+		if self.dmp_advancement_rule_min_processed == 0 {
+			return Err(ZeroDmpAdvancementRuleMinProcessed)
+		}
+
 		Ok(())
 	}
 
@@ -1183,6 +1219,68 @@ pub mod pallet {
 				config.executor_params = new;
 			})
 		}
+
+		/// Sets the maximum total size of messages in bytes allowed in a downward message queue.
+		#[pallet::call_index(47)]
+		#[pallet::weight((
+			T::WeightInfo::set_config_with_u32(),
+			DispatchClass::Operational,
+		))]
+		pub fn set_max_downward_queue_bytes(origin: OriginFor<T>, new: u32) -> DispatchResult {
+			ensure_root(origin)?;
+			Self::schedule_config_update(|config| {
+				config.max_downward_queue_bytes = new;
+			})
+		}
+
+		/// Sets the minimum number of downward messages a candidate has to process if its para
+		/// has pending downward messages.
+		#[pallet::call_index(48)]
+		#[pallet::weight((
+			T::WeightInfo::set_config_with_u32(),
+			DispatchClass::Operational,
+		))]
+		pub fn set_dmp_advancement_rule_min_processed(
+			origin: OriginFor<T>,
+			new: u32,
+		) -> DispatchResult {
+			ensure_root(origin)?;
+			Self::schedule_config_update(|config| {
+				config.dmp_advancement_rule_min_processed = new;
+			})
+		}
+
+		/// Sets the number of blocks after which pending downward messages expire.
+		#[pallet::call_index(49)]
+		#[pallet::weight((
+			T::WeightInfo::set_config_with_block_number(),
+			DispatchClass::Operational,
+		))]
+		pub fn set_downward_message_ttl(
+			origin: OriginFor<T>,
+			new: T::BlockNumber,
+		) -> DispatchResult {
+			ensure_root(origin)?;
+			Self::schedule_config_update(|config| {
+				config.downward_message_ttl = new;
+			})
+		}
+
+		/// Sets whether downward messages to paras that are being offboarded are rejected.
+		#[pallet::call_index(50)]
+		#[pallet::weight((
+			T::WeightInfo::set_config_with_u32(),
+			DispatchClass::Operational,
+		))]
+		pub fn set_dmp_reject_offboarding_recipients(
+			origin: OriginFor<T>,
+			new: bool,
+		) -> DispatchResult {
+			ensure_root(origin)?;
+			Self::schedule_config_update(|config| {
+				config.dmp_reject_offboarding_recipients = new;
+			})
+		}
 	}
 
 	#[pallet::hooks]
@@ -227,6 +227,10 @@ fn invariants() {
 			Configuration::set_no_show_slots(RuntimeOrigin::root(), 0),
 			Error::<Test>::InvalidNewValue
 		);
+		assert_err!(
+			Configuration::set_dmp_advancement_rule_min_processed(RuntimeOrigin::root(), 0),
+			Error::<Test>::InvalidNewValue
+		);
 
 		ActiveConfig::<Test>::put(HostConfiguration {
 			chain_availability_period: 10,
@@ -309,6 +313,10 @@ fn setting_pending_config_members() {
 			max_upward_queue_count: 1337,
 			max_upward_queue_size: 228,
 			max_downward_message_size: 2048,
+			max_downward_queue_bytes: 65536,
+			dmp_advancement_rule_min_processed: 2,
+			downward_message_ttl: 14_400,
+			dmp_reject_offboarding_recipients: true,
 			ump_service_total_weight: Weight::from_parts(20000, 20000),
 			max_upward_message_size: 448,
 			max_upward_message_num_per_candidate: 5,
@@ -423,6 +431,26 @@ fn setting_pending_config_members() {
 			new_config.max_downward_message_size,
 		)
 		.unwrap();
+		Configuration::set_max_downward_queue_bytes(
+			RuntimeOrigin::root(),
+			new_config.max_downward_queue_bytes,
+		)
+		.unwrap();
+		Configuration::set_dmp_advancement_rule_min_processed(
+			RuntimeOrigin::root(),
+			new_config.dmp_advancement_rule_min_processed,
+		)
+		.unwrap();
+		Configuration::set_downward_message_ttl(
+			RuntimeOrigin::root(),
+			new_config.downward_message_ttl,
+		)
+		.unwrap();
+		Configuration::set_dmp_reject_offboarding_recipients(
+			RuntimeOrigin::root(),
+			new_config.dmp_reject_offboarding_recipients,
+		)
+		.unwrap();
 		Configuration::set_ump_service_total_weight(
 			RuntimeOrigin::root(),
 			new_config.ump_service_total_weight,
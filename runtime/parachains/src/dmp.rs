@@ -44,62 +44,135 @@
 
 use crate::{
 	configuration::{self, HostConfiguration},
-	initializer, FeeTracker,
+	initializer, paras, FeeTracker,
+};
+use frame_support::{
+	pallet_prelude::*,
+	traits::Contains,
+	weights::{constants::RocksDbWeight, WeightMeter},
+};
+use primitives::{
+	vstaging::{DmqCapacity, DmqStats},
+	DownwardMessage, Hash, Id as ParaId, InboundDownwardMessage,
 };
-use frame_support::pallet_prelude::*;
-use primitives::{DownwardMessage, Hash, Id as ParaId, InboundDownwardMessage};
 use sp_core::MAX_POSSIBLE_ALLOCATION;
 use sp_runtime::{
-	traits::{BlakeTwo256, Hash as HashT, SaturatedConversion},
+	traits::{BlakeTwo256, Hash as HashT, SaturatedConversion, Zero},
 	FixedU128, Saturating,
 };
-use sp_std::{fmt, prelude::*};
+use sp_std::prelude::*;
 use xcm::latest::SendError;
 
 pub use pallet::*;
+pub use primitives::vstaging::ProcessedDownwardMessagesAcceptanceErr;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+pub mod migration;
 
 #[cfg(test)]
 mod tests;
 
+const LOG_TARGET: &str = "runtime::dmp";
+
 const THRESHOLD_FACTOR: u32 = 2;
 const EXPONENTIAL_FEE_BASE: FixedU128 = FixedU128::from_rational(105, 100); // 1.05
 const MESSAGE_SIZE_FEE_BASE: FixedU128 = FixedU128::from_rational(1, 1000); // 0.001
 
+/// The maximum number of downward message queues checked for expired messages in a block.
+const MAX_EXPIRY_CHECKS_PER_BLOCK: u32 = 100;
+
+/// Weight information of this pallet.
+pub trait WeightInfo {
+	fn prune_dmq(m: u32) -> Weight;
+	fn clean_dmp_after_outgoing() -> Weight;
+	fn force_prune_dmq(m: u32) -> Weight;
+	fn sweep_dangling_dmq() -> Weight;
+	fn queue_downward_message(s: u32) -> Weight;
+	fn initializer_initialize(p: u32) -> Weight;
+}
+
+/// Weights that only account for the storage accesses, until the runtimes use the weights generated
+/// by the benchmarks of this pallet.
+impl WeightInfo for () {
+	fn prune_dmq(_m: u32) -> Weight {
+		RocksDbWeight::get().reads_writes(5, 5)
+	}
+
+	fn clean_dmp_after_outgoing() -> Weight {
+		RocksDbWeight::get().writes(6)
+	}
+
+	fn force_prune_dmq(_m: u32) -> Weight {
+		RocksDbWeight::get().reads_writes(7, 6)
+	}
+
+	fn sweep_dangling_dmq() -> Weight {
+		RocksDbWeight::get().reads_writes(3, 6)
+	}
+
+	fn queue_downward_message(_s: u32) -> Weight {
+		RocksDbWeight::get().reads_writes(6, 6)
+	}
+
+	fn initializer_initialize(p: u32) -> Weight {
+		RocksDbWeight::get().reads_writes(2 + p as u64, 1)
+	}
+}
+
+/// fallback implementation
+pub struct TestWeightInfo;
+impl WeightInfo for TestWeightInfo {
+	fn prune_dmq(_m: u32) -> Weight {
+		// Called during candidate enactment in integration tests, where weights are summed up.
+		Weight::zero()
+	}
+
+	fn clean_dmp_after_outgoing() -> Weight {
+		Weight::MAX
+	}
+
+	fn force_prune_dmq(_m: u32) -> Weight {
+		Weight::MAX
+	}
+
+	fn sweep_dangling_dmq() -> Weight {
+		Weight::MAX
+	}
+
+	fn queue_downward_message(_s: u32) -> Weight {
+		// Weighs `sudo_queue_downward_xcm`, which test runtimes use to send downward messages.
+		Weight::zero()
+	}
+
+	fn initializer_initialize(_p: u32) -> Weight {
+		// Called during block initialization in integration tests, where weights are summed up.
+		Weight::zero()
+	}
+}
+
 /// An error sending a downward message.
 #[cfg_attr(test, derive(Debug))]
 pub enum QueueDownwardMessageError {
 	/// The message being sent exceeds the configured max message size.
 	ExceedsMaxMessageSize,
+	/// The message being sent would make the queue exceed the configured max queue size in bytes.
+	QueueBytesExceeded,
+	/// The recipient is being offboarded, so its queue is about to be removed.
+	RecipientOffboarding,
+	/// The queue of the recipient already holds the maximum number of pending messages allowed by
+	/// the runtime.
+	QueueLimitReached,
 }
 
 impl From<QueueDownwardMessageError> for SendError {
 	fn from(err: QueueDownwardMessageError) -> Self {
 		match err {
-			QueueDownwardMessageError::ExceedsMaxMessageSize => SendError::ExceedsMaxMessageSize,
-		}
-	}
-}
-
-/// An error returned by [`check_processed_downward_messages`] that indicates an acceptance check
-/// didn't pass.
-pub enum ProcessedDownwardMessagesAcceptanceErr {
-	/// If there are pending messages then `processed_downward_messages` should be at least 1,
-	AdvancementRule,
-	/// `processed_downward_messages` should not be greater than the number of pending messages.
-	Underflow { processed_downward_messages: u32, dmq_length: u32 },
-}
-
-impl fmt::Debug for ProcessedDownwardMessagesAcceptanceErr {
-	fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-		use ProcessedDownwardMessagesAcceptanceErr::*;
-		match *self {
-			AdvancementRule =>
-				write!(fmt, "DMQ is not empty, but processed_downward_messages is 0",),
-			Underflow { processed_downward_messages, dmq_length } => write!(
-				fmt,
-				"processed_downward_messages = {}, but dmq_length is only {}",
-				processed_downward_messages, dmq_length,
-			),
+			QueueDownwardMessageError::ExceedsMaxMessageSize |
+			QueueDownwardMessageError::QueueBytesExceeded => SendError::ExceedsMaxMessageSize,
+			QueueDownwardMessageError::QueueLimitReached =>
+				SendError::Transport("Downward message queue is full"),
+			QueueDownwardMessageError::RecipientOffboarding => SendError::Unroutable,
 		}
 	}
 }
@@ -107,13 +180,61 @@ impl fmt::Debug for ProcessedDownwardMessagesAcceptanceErr {
 #[frame_support::pallet]
 pub mod pallet {
 	use super::*;
+	use frame_system::pallet_prelude::*;
 
 	#[pallet::pallet]
+	#[pallet::storage_version(migration::STORAGE_VERSION)]
 	#[pallet::without_storage_info]
 	pub struct Pallet<T>(_);
 
 	#[pallet::config]
-	pub trait Config: frame_system::Config + configuration::Config {}
+	pub trait Config: frame_system::Config + configuration::Config + paras::Config {
+		/// The aggregate event.
+		type RuntimeEvent: From<Event> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// Weight information for extrinsics in this pallet.
+		type WeightInfo: WeightInfo;
+
+		/// The paras that have a candidate pending availability.
+		///
+		/// Once such a candidate is enacted, the messages it processed are removed from the front
+		/// of the queue of its para, so no other messages may be removed from the queue before.
+		type ParasPendingAvailability: Contains<ParaId>;
+
+		/// The maximum number of messages that can be pending in the downward message queue of a
+		/// para.
+		///
+		/// This is a policy limit on top of the limits derived from the host configuration.
+		#[pallet::constant]
+		type MaxPendingDownwardMessages: Get<u32>;
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event {
+		/// Downward messages have been processed by a para and were pruned from its queue.
+		/// \[ para, count, remaining \]
+		DownwardMessagesProcessed(ParaId, u32, u32),
+		/// Downward messages have expired before being processed and were dropped from the queue
+		/// of a para.
+		/// \[ para, count, remaining \]
+		DownwardMessagesExpired(ParaId, u32, u32),
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The number of messages to prune is greater than the number of pending messages.
+		PruneCountExceedsQueueLength,
+		/// The given queue length witness is lower than the actual length of the queue.
+		WrongQueueLengthWitness,
+		/// The para is registered, so its downward message queue is not dangling.
+		ParaIsLive,
+		/// There is no downward message queue storage for the para.
+		NoDanglingQueue,
+		/// A candidate of the para is pending availability, which removes the messages it
+		/// processed from the queue once it is enacted.
+		CandidatePendingAvailability,
+	}
 
 	/// The downward messages addressed for a certain para.
 	#[pallet::storage]
@@ -125,6 +246,23 @@ pub mod pallet {
 		ValueQuery,
 	>;
 
+	/// The total size in bytes of the messages in the downward message queue of a para.
+	///
+	/// Kept in sync with `DownwardMessageQueues`, so that the size of a queue can be checked
+	/// without decoding it.
+	#[pallet::storage]
+	pub(crate) type DownwardMessageQueueBytes<T: Config> =
+		StorageMap<_, Twox64Concat, ParaId, u64, ValueQuery>;
+
+	/// The block number at which the oldest pending message in the downward message queue of a para
+	/// was sent.
+	///
+	/// Kept in sync with `DownwardMessageQueues`, so that the queues with expired messages can be
+	/// found without decoding them. `None` if the queue is empty.
+	#[pallet::storage]
+	pub(crate) type DownwardMessageQueueOldestSentAt<T: Config> =
+		StorageMap<_, Twox64Concat, ParaId, T::BlockNumber, OptionQuery>;
+
 	/// A mapping that stores the downward message queue MQC head for each para.
 	///
 	/// Each link in this chain has a form:
@@ -136,6 +274,40 @@ pub mod pallet {
 	pub(crate) type DownwardMessageQueueHeads<T: Config> =
 		StorageMap<_, Twox64Concat, ParaId, Hash, ValueQuery>;
 
+	/// The MQC head after the last message that was removed from the downward message queue of a
+	/// para.
+	///
+	/// Linking the pending messages on top of it results in the MQC head, which allows a para to
+	/// restart the verification of its pending messages. This is the zero hash as long as no
+	/// message was removed from the chain.
+	///
+	/// Messages that are removed without being processed by the para, because they expired or
+	/// were pruned by Root, leave a gap: the pending messages don't continue the MQC from the head
+	/// that the para processed last. The para re-anchors the verification on this head instead,
+	/// which its collators can prove to it with a relay chain state proof.
+	///
+	/// `None` if it is unknown. That is the case for a queue that had messages removed before this
+	/// started being tracked, until it is drained.
+	#[pallet::storage]
+	pub(crate) type LastPrunedMqcHead<T: Config> =
+		StorageMap<_, Twox64Concat, ParaId, Hash, OptionQuery>;
+
+	/// The paras that were offboarded, whose downward message queue storage is removed again in
+	/// `on_idle`, in case messages were sent to them in the meantime.
+	///
+	/// A para is removed from the set once it was checked. Messages that are sent to it afterwards
+	/// are left to `sweep_dangling_dmq`.
+	#[pallet::storage]
+	pub(crate) type OffboardedParas<T: Config> =
+		StorageMap<_, Twox64Concat, ParaId, (), OptionQuery>;
+
+	/// The raw storage key of the last para in `DownwardMessageQueueOldestSentAt` that was checked
+	/// for expired messages.
+	///
+	/// The next check continues after it. `None` starts over from the first para.
+	#[pallet::storage]
+	pub(crate) type ExpiryCursor<T: Config> = StorageValue<_, Vec<u8>, OptionQuery>;
+
 	/// Initialization value for the DeliveryFee factor.
 	#[pallet::type_value]
 	pub fn InitialFactor() -> FixedU128 {
@@ -146,12 +318,101 @@ pub mod pallet {
 	#[pallet::storage]
 	pub(crate) type DeliveryFeeFactor<T: Config> =
 		StorageMap<_, Twox64Concat, ParaId, FixedU128, ValueQuery, InitialFactor>;
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_idle(_now: BlockNumberFor<T>, remaining_weight: Weight) -> Weight {
+			let weight = <T as Config>::WeightInfo::sweep_dangling_dmq()
+				.saturating_add(T::DbWeight::get().reads_writes(1, 1));
+			if !remaining_weight.all_gte(weight) {
+				return Weight::zero()
+			}
+			if Self::idle_cleanup_step() {
+				weight
+			} else {
+				T::DbWeight::get().reads(1)
+			}
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn try_state(_: BlockNumberFor<T>) -> Result<(), &'static str> {
+			Self::do_try_state()
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Remove the first `count` messages from the downward message queue of `para`, as if
+		/// they were processed by the para.
+		///
+		/// This allows cleaning up the queue of a para that stalled without offboarding it. The
+		/// MQC head is left intact, exactly like when messages are processed. The pruned messages
+		/// are never delivered though, so the remaining messages don't continue the MQC from the
+		/// head that the para processed last. The para has to re-anchor the verification on
+		/// [`LastPrunedMqcHead`].
+		///
+		/// Origin must be Root.
+		///
+		/// The length of the queue of `para` must be provided as witness data of weighing.
+		///
+		/// Errors:
+		/// - `CandidatePendingAvailability`: A candidate of the para is pending availability.
+		/// - `WrongQueueLengthWitness`: The queue is longer than `queue_len`.
+		/// - `PruneCountExceedsQueueLength`: The queue has fewer than `count` messages.
+		///
+		/// Events:
+		/// - `DownwardMessagesProcessed`: If any messages were pruned.
+		#[pallet::call_index(0)]
+		#[pallet::weight(<T as Config>::WeightInfo::force_prune_dmq(*queue_len))]
+		pub fn force_prune_dmq(
+			origin: OriginFor<T>,
+			para: ParaId,
+			count: u32,
+			queue_len: u32,
+		) -> DispatchResult {
+			ensure_root(origin)?;
+			ensure!(
+				!T::ParasPendingAvailability::contains(&para),
+				Error::<T>::CandidatePendingAvailability
+			);
+			let dmq_length = Self::dmq_length(para);
+			ensure!(dmq_length <= queue_len, Error::<T>::WrongQueueLengthWitness);
+			ensure!(count <= dmq_length, Error::<T>::PruneCountExceedsQueueLength);
+			Self::prune_dmq(para, count);
+			Ok(())
+		}
+
+		/// Remove the downward message queue storage of a para that is not registered.
+		///
+		/// Downward messages can be sent to a para that doesn't exist, which leaves dangling
+		/// storage behind. Paras that are not registered may still be reserved, so only Root can
+		/// decide that the messages sent to them can be dropped.
+		///
+		/// Origin must be Root.
+		///
+		/// Errors:
+		/// - `ParaIsLive`: The para is onboarded or pending onboarding.
+		/// - `NoDanglingQueue`: There is no downward message queue storage for the para.
+		#[pallet::call_index(1)]
+		#[pallet::weight(<T as Config>::WeightInfo::sweep_dangling_dmq())]
+		pub fn sweep_dangling_dmq(origin: OriginFor<T>, para: ParaId) -> DispatchResult {
+			ensure_root(origin)?;
+			ensure!(paras::Pallet::<T>::lifecycle(para).is_none(), Error::<T>::ParaIsLive);
+			ensure!(
+				DownwardMessageQueues::<T>::contains_key(&para) ||
+					DownwardMessageQueueHeads::<T>::contains_key(&para),
+				Error::<T>::NoDanglingQueue
+			);
+			Self::clean_dmp_after_outgoing(&para);
+			Ok(())
+		}
+	}
 }
 /// Routines and getters related to downward message passing.
 impl<T: Config> Pallet<T> {
 	/// Block initialization logic, called by initializer.
-	pub(crate) fn initializer_initialize(_now: T::BlockNumber) -> Weight {
-		Weight::zero()
+	pub(crate) fn initializer_initialize(now: T::BlockNumber) -> Weight {
+		Self::expire_downward_messages(now)
 	}
 
 	/// Block finalization logic, called by initializer.
@@ -161,34 +422,135 @@ impl<T: Config> Pallet<T> {
 	pub(crate) fn initializer_on_new_session(
 		_notification: &initializer::SessionChangeNotification<T::BlockNumber>,
 		outgoing_paras: &[ParaId],
-	) {
-		Self::perform_outgoing_para_cleanup(outgoing_paras);
+	) -> Weight {
+		Self::perform_outgoing_para_cleanup(outgoing_paras)
 	}
 
 	/// Iterate over all paras that were noted for offboarding and remove all the data
 	/// associated with them.
-	fn perform_outgoing_para_cleanup(outgoing: &[ParaId]) {
+	fn perform_outgoing_para_cleanup(outgoing: &[ParaId]) -> Weight {
+		let mut weight: Weight = Weight::zero();
 		for outgoing_para in outgoing {
-			Self::clean_dmp_after_outgoing(outgoing_para);
+			weight = weight.saturating_add(Self::clean_dmp_after_outgoing(outgoing_para));
+			OffboardedParas::<T>::insert(outgoing_para, ());
 		}
+		weight.saturating_add(T::DbWeight::get().writes(outgoing.len() as u64))
 	}
 
 	/// Remove all relevant storage items for an outgoing parachain.
-	fn clean_dmp_after_outgoing(outgoing_para: &ParaId) {
+	pub(crate) fn clean_dmp_after_outgoing(outgoing_para: &ParaId) -> Weight {
 		DownwardMessageQueues::<T>::remove(outgoing_para);
+		DownwardMessageQueueBytes::<T>::remove(outgoing_para);
+		DownwardMessageQueueOldestSentAt::<T>::remove(outgoing_para);
 		DownwardMessageQueueHeads::<T>::remove(outgoing_para);
+		LastPrunedMqcHead::<T>::remove(outgoing_para);
+		OffboardedParas::<T>::remove(outgoing_para);
+
+		T::WeightInfo::clean_dmp_after_outgoing()
+	}
+
+	/// Drops the downward messages that have been pending for longer than the configured
+	/// `downward_message_ttl`.
+	///
+	/// At most `MAX_EXPIRY_CHECKS_PER_BLOCK` queues are checked per block, in storage order. The
+	/// next block continues after the last checked queue, so with many queues messages can be
+	/// dropped a few blocks after they expired. Only `DownwardMessageQueueOldestSentAt` is read to
+	/// find out whether any messages of a queue expired.
+	///
+	/// A queue is left alone while a candidate of its para is pending availability, since enacting
+	/// the candidate removes the messages it processed from the front of the queue.
+	///
+	/// The MQC head is left intact, exactly like when messages are processed. The dropped messages
+	/// are linked into `LastPrunedMqcHead`, which the para re-anchors the verification of the
+	/// remaining messages on.
+	///
+	/// The returned weight accounts for the number of queues that were checked and the messages
+	/// that were dropped.
+	fn expire_downward_messages(now: T::BlockNumber) -> Weight {
+		let ttl = configuration::ActiveConfig::<T>::get().downward_message_ttl;
+		if ttl.is_zero() {
+			return T::WeightInfo::initializer_initialize(0)
+		}
+
+		let is_expired = |sent_at: T::BlockNumber| now.saturating_sub(sent_at) > ttl;
+		let cursor = ExpiryCursor::<T>::get();
+		let mut queues = match cursor.clone() {
+			Some(cursor) => DownwardMessageQueueOldestSentAt::<T>::iter_from(cursor),
+			None => DownwardMessageQueueOldestSentAt::<T>::iter(),
+		};
+		let mut checked = 0u32;
+		let mut weight = Weight::zero();
+
+		for (para, oldest_sent_at) in queues.by_ref().take(MAX_EXPIRY_CHECKS_PER_BLOCK as usize) {
+			checked.saturating_inc();
+			if !is_expired(oldest_sent_at) {
+				continue
+			}
+			weight.saturating_accrue(T::DbWeight::get().reads(1));
+			if T::ParasPendingAvailability::contains(&para) {
+				continue
+			}
+
+			let (expired, q_len) = Self::remove_dmq_prefix(para, |q| {
+				q.iter().take_while(|msg| is_expired(msg.sent_at)).count()
+			});
+			let expired = expired.len();
+			let prev_q_len = expired.saturating_add(q_len);
+			log::trace!(
+				target: LOG_TARGET,
+				"expired downward messages para={} expired={} queue_len={}",
+				u32::from(para),
+				expired,
+				q_len,
+			);
+			Self::deposit_event(Event::DownwardMessagesExpired(
+				para,
+				expired.saturated_into::<u32>(),
+				q_len.saturated_into::<u32>(),
+			));
+			weight.saturating_accrue(T::WeightInfo::prune_dmq(prev_q_len.saturated_into::<u32>()));
+		}
+
+		if checked == MAX_EXPIRY_CHECKS_PER_BLOCK {
+			ExpiryCursor::<T>::put(queues.last_raw_key().to_vec());
+		} else if cursor.is_some() {
+			// all the remaining queues were checked, start over in the next block.
+			ExpiryCursor::<T>::kill();
+		}
+
+		weight.saturating_add(T::WeightInfo::initializer_initialize(checked))
 	}
 
-	/// Determine whether enqueuing a downward message to a specific recipient para would result
-	/// in an error. If this returns `Ok(())` the caller can be certain that a call to
-	/// `queue_downward_message` with the same parameters will be successful.
+	/// Checks the next para in `OffboardedParas` and removes it from the set, along with its
+	/// downward message queue storage unless the para was registered again.
+	///
+	/// Paras that were never registered are left alone, their IDs may be reserved and the messages
+	/// sent to them still be processed once they are onboarded.
+	///
+	/// Returns `false` if there was no para left to check.
+	pub(crate) fn idle_cleanup_step() -> bool {
+		let para = match OffboardedParas::<T>::iter_keys().next() {
+			Some(para) => para,
+			None => return false,
+		};
+		if paras::Pallet::<T>::lifecycle(para).is_none() {
+			Self::clean_dmp_after_outgoing(&para);
+		}
+		OffboardedParas::<T>::remove(para);
+		true
+	}
+
+	/// Determine whether enqueuing a downward message of `msg_len` bytes to a specific recipient
+	/// para would result in an error. If this returns `Ok(())` the caller can be certain that a
+	/// call to `queue_downward_message` with the same parameters will be successful.
+	///
+	/// This only reads storage, so it can be used to fail fast before charging for delivery.
 	pub fn can_queue_downward_message(
 		config: &HostConfiguration<T::BlockNumber>,
 		para: &ParaId,
-		msg: &DownwardMessage,
+		msg_len: u32,
 	) -> Result<(), QueueDownwardMessageError> {
-		let serialized_len = msg.len() as u32;
-		if serialized_len > config.max_downward_message_size {
+		if msg_len > config.max_downward_message_size {
 			return Err(QueueDownwardMessageError::ExceedsMaxMessageSize)
 		}
 
@@ -197,6 +559,22 @@ impl<T: Config> Pallet<T> {
 			return Err(QueueDownwardMessageError::ExceedsMaxMessageSize)
 		}
 
+		if Self::dmq_length(*para) >= T::MaxPendingDownwardMessages::get() {
+			return Err(QueueDownwardMessageError::QueueLimitReached)
+		}
+
+		if Self::dmq_total_bytes(*para).saturating_add(msg_len as u64) >
+			config.max_downward_queue_bytes as u64
+		{
+			return Err(QueueDownwardMessageError::QueueBytesExceeded)
+		}
+
+		if config.dmp_reject_offboarding_recipients &&
+			paras::Pallet::<T>::lifecycle(*para).map_or(false, |l| l.is_offboarding())
+		{
+			return Err(QueueDownwardMessageError::RecipientOffboarding)
+		}
+
 		Ok(())
 	}
 
@@ -214,30 +592,40 @@ impl<T: Config> Pallet<T> {
 		msg: DownwardMessage,
 	) -> Result<(), QueueDownwardMessageError> {
 		let serialized_len = msg.len() as u32;
-		if serialized_len > config.max_downward_message_size {
-			return Err(QueueDownwardMessageError::ExceedsMaxMessageSize)
-		}
-
-		// Hard limit on Queue size
-		if Self::dmq_length(para) > Self::dmq_max_length(config.max_downward_message_size) {
-			return Err(QueueDownwardMessageError::ExceedsMaxMessageSize)
-		}
+		Self::can_queue_downward_message(config, &para, serialized_len)?;
 
-		let inbound =
-			InboundDownwardMessage { msg, sent_at: <frame_system::Pallet<T>>::block_number() };
+		let now = <frame_system::Pallet<T>>::block_number();
+		let inbound = InboundDownwardMessage { msg, sent_at: now };
 
 		// obtain the new link in the MQC and update the head.
 		DownwardMessageQueueHeads::<T>::mutate(para, |head| {
-			let new_head =
-				BlakeTwo256::hash_of(&(*head, inbound.sent_at, T::Hashing::hash_of(&inbound.msg)));
-			*head = new_head;
+			if head.is_zero() {
+				// the chain starts over, no message was removed from it yet.
+				LastPrunedMqcHead::<T>::insert(para, Hash::zero());
+			}
+			*head = Self::mqc_link(*head, &inbound);
 		});
 
-		let q_len = DownwardMessageQueues::<T>::mutate(para, |v| {
-			v.push(inbound);
-			v.len()
+		// appending avoids decoding and re-encoding the messages that are already queued.
+		DownwardMessageQueues::<T>::append(para, inbound);
+		let q_len = DownwardMessageQueues::<T>::decode_len(para).unwrap_or(0);
+		if q_len == 1 {
+			DownwardMessageQueueOldestSentAt::<T>::insert(para, now);
+		}
+		let q_bytes = DownwardMessageQueueBytes::<T>::mutate(para, |bytes| {
+			*bytes = bytes.saturating_add(serialized_len as u64);
+			*bytes
 		});
 
+		log::trace!(
+			target: LOG_TARGET,
+			"queued downward message para={} msg_len={} queue_len={} queue_bytes={}",
+			u32::from(para),
+			serialized_len,
+			q_len,
+			q_bytes,
+		);
+
 		let threshold =
 			Self::dmq_max_length(config.max_downward_message_size).saturating_div(THRESHOLD_FACTOR);
 		if q_len > (threshold as usize) {
@@ -252,16 +640,26 @@ impl<T: Config> Pallet<T> {
 
 	/// Checks if the number of processed downward messages is valid.
 	pub(crate) fn check_processed_downward_messages(
+		config: &HostConfiguration<T::BlockNumber>,
 		para: ParaId,
 		processed_downward_messages: u32,
 	) -> Result<(), ProcessedDownwardMessagesAcceptanceErr> {
 		let dmq_length = Self::dmq_length(para);
+		let required = config.dmp_advancement_rule_min_processed.min(dmq_length);
 
-		if dmq_length > 0 && processed_downward_messages == 0 {
-			return Err(ProcessedDownwardMessagesAcceptanceErr::AdvancementRule)
+		if required > 0 && processed_downward_messages == 0 {
+			return Err(ProcessedDownwardMessagesAcceptanceErr::AdvancementRule { para })
+		}
+		if processed_downward_messages < required {
+			return Err(ProcessedDownwardMessagesAcceptanceErr::AdvancementRuleMinimum {
+				para,
+				processed_downward_messages,
+				required,
+			})
 		}
 		if dmq_length < processed_downward_messages {
 			return Err(ProcessedDownwardMessagesAcceptanceErr::Underflow {
+				para,
 				processed_downward_messages,
 				dmq_length,
 			})
@@ -271,35 +669,180 @@ impl<T: Config> Pallet<T> {
 	}
 
 	/// Prunes the specified number of messages from the downward message queue of the given para.
+	///
+	/// The returned weight depends on the number of messages that were pending before pruning.
 	pub(crate) fn prune_dmq(para: ParaId, processed_downward_messages: u32) -> Weight {
-		let q_len = DownwardMessageQueues::<T>::mutate(para, |q| {
-			let processed_downward_messages = processed_downward_messages as usize;
-			if processed_downward_messages > q.len() {
-				// reaching this branch is unexpected due to the constraint established by
-				// `check_processed_downward_messages`. But better be safe than sorry.
-				q.clear();
+		Self::take_dmq_prefix(para, |_| processed_downward_messages as usize).1
+	}
+
+	/// Prunes the first messages of the downward message queue of the given para, like
+	/// [`Self::prune_dmq`], and returns them along with the weight of pruning.
+	///
+	/// The number of messages to prune is computed by `count` from the queue, see
+	/// [`Self::remove_dmq_prefix`].
+	fn take_dmq_prefix(
+		para: ParaId,
+		count: impl FnOnce(&[InboundDownwardMessage<T::BlockNumber>]) -> usize,
+	) -> (Vec<InboundDownwardMessage<T::BlockNumber>>, Weight) {
+		let (pruned_msgs, q_len) = Self::remove_dmq_prefix(para, count);
+
+		let pruned = pruned_msgs.len();
+		log::trace!(
+			target: LOG_TARGET,
+			"pruned downward messages para={} pruned={} queue_len={} queue_bytes={}",
+			u32::from(para),
+			pruned,
+			q_len,
+			Self::dmq_total_bytes(para),
+		);
+		if pruned > 0 {
+			Self::deposit_event(Event::DownwardMessagesProcessed(
+				para,
+				pruned.saturated_into::<u32>(),
+				q_len.saturated_into::<u32>(),
+			));
+		}
+
+		let prev_q_len = pruned.saturating_add(q_len);
+		(pruned_msgs, T::WeightInfo::prune_dmq(prev_q_len.saturated_into::<u32>()))
+	}
+
+	/// Removes the first messages from the downward message queue of the given para and updates
+	/// the queue size, the oldest message, the last pruned MQC head and the delivery fee factor
+	/// accordingly.
+	///
+	/// The number of messages to remove is computed by `count` from the queue, which is only read
+	/// once, and only written back if any messages are removed. Returns the removed messages, oldest
+	/// first, along with the length of the queue after the removal.
+	///
+	/// Unless the whole queue is removed, every removed message is hashed to advance the last
+	/// pruned MQC head, if it is known. The per-message weight of `prune_dmq` accounts for that, as
+	/// its benchmark keeps the newest message of a queue filled with messages of the maximum size.
+	fn remove_dmq_prefix(
+		para: ParaId,
+		count: impl FnOnce(&[InboundDownwardMessage<T::BlockNumber>]) -> usize,
+	) -> (Vec<InboundDownwardMessage<T::BlockNumber>>, usize) {
+		let mut oldest = None;
+		let removal = DownwardMessageQueues::<T>::try_mutate(para, |q| {
+			// reaching the upper bound is unexpected due to the constraint established by
+			// `check_processed_downward_messages`. But better be safe than sorry.
+			let count = count(q).min(q.len());
+			if count == 0 {
+				return Err(q.len())
+			}
+			let pruned_bytes = q.iter().take(count).map(|m| m.msg.len() as u64).sum::<u64>();
+			if count == q.len() {
+				// the last removed message is the newest one, so the MQC head already links it.
+				LastPrunedMqcHead::<T>::insert(para, DownwardMessageQueueHeads::<T>::get(para));
 			} else {
-				*q = q.split_off(processed_downward_messages);
+				// if the head is unknown, the first removed message isn't necessarily linked to the
+				// zero hash, so the head can't be derived from the removed messages.
+				if let Some(head) = LastPrunedMqcHead::<T>::get(para) {
+					let head = q.iter().take(count).fold(head, |h, m| Self::mqc_link(h, m));
+					LastPrunedMqcHead::<T>::insert(para, head);
+				}
 			}
-			q.len()
+			let rest = q.split_off(count);
+			let removed = sp_std::mem::replace(q, rest);
+			oldest = q.first().map(|m| m.sent_at);
+			Ok((removed, q.len(), pruned_bytes))
 		});
 
+		let (removed, q_len) = match removal {
+			Ok((removed, q_len, pruned_bytes)) => {
+				if oldest.is_none() {
+					DownwardMessageQueueBytes::<T>::remove(para);
+					DownwardMessageQueueOldestSentAt::<T>::remove(para);
+				} else {
+					DownwardMessageQueueBytes::<T>::mutate(para, |bytes| {
+						*bytes = bytes.saturating_sub(pruned_bytes);
+					});
+					DownwardMessageQueueOldestSentAt::<T>::set(para, oldest);
+				}
+				(removed, q_len)
+			},
+			Err(q_len) => (Vec::new(), q_len),
+		};
+
 		let config = configuration::ActiveConfig::<T>::get();
 		let threshold =
 			Self::dmq_max_length(config.max_downward_message_size).saturating_div(THRESHOLD_FACTOR);
 		if q_len <= (threshold as usize) {
 			Self::decrement_fee_factor(para);
 		}
-		T::DbWeight::get().reads_writes(1, 1)
+		(removed, q_len)
+	}
+
+	/// Drains messages from the head of the downward message queue of the given para for as long as
+	/// the weight estimated by `per_message` fits into `meter`.
+	///
+	/// Draining stops at the first message that doesn't fit. The drained messages are pruned the
+	/// same way as in [`Self::prune_dmq`], with the queue only being read once. Returns the drained
+	/// messages, oldest first.
+	pub fn drain_dmq_weighted(
+		para: ParaId,
+		meter: &mut WeightMeter,
+		per_message: impl Fn(&InboundDownwardMessage<T::BlockNumber>) -> Weight,
+	) -> Vec<InboundDownwardMessage<T::BlockNumber>> {
+		Self::take_dmq_prefix(para, |q| {
+			q.iter().take_while(|msg| meter.check_accrue(per_message(msg))).count()
+		})
+		.0
+	}
+
+	/// Removes up to `max_messages` messages from the head of the downward message queue of the
+	/// given para and returns them, oldest first.
+	///
+	/// The messages are pruned the same way as in [`Self::prune_dmq`], while the queue is read, so
+	/// the returned messages are exactly the ones that were removed from the queue.
+	pub fn take_dmq(
+		para: ParaId,
+		max_messages: u32,
+	) -> Vec<InboundDownwardMessage<T::BlockNumber>> {
+		Self::take_dmq_prefix(para, |_| max_messages as usize).0
 	}
 
-	/// Returns the Head of Message Queue Chain for the given para or `None` if there is none
-	/// associated with it.
-	#[cfg(test)]
-	fn dmq_mqc_head(para: ParaId) -> Hash {
+	/// Returns the Head of Message Queue Chain for the given para.
+	///
+	/// Returns the zero hash if the para doesn't have an associated downward message queue, which
+	/// matches the genesis state of the chain.
+	pub(crate) fn dmq_mqc_head(para: ParaId) -> Hash {
 		DownwardMessageQueueHeads::<T>::get(&para)
 	}
 
+	/// Returns the MQC head after the last message that was removed from the downward message
+	/// queue of the given para, if known.
+	///
+	/// This is the zero hash if no message was removed from the chain yet.
+	///
+	/// [`Self::verify_mqc_continuity`] passes for it.
+	pub fn dmq_last_pruned_mqc_head(para: ParaId) -> Option<Hash> {
+		LastPrunedMqcHead::<T>::get(&para)
+	}
+
+	/// Checks that the pending downward messages of the given para continue the MQC from
+	/// `last_processed_head`.
+	///
+	/// `last_processed_head` is the MQC head after the last message that the para processed. The
+	/// check passes if hashing the pending messages on top of it results in the current MQC head.
+	pub fn verify_mqc_continuity(para: ParaId, last_processed_head: Hash) -> bool {
+		let head = DownwardMessageQueues::<T>::get(&para)
+			.iter()
+			.fold(last_processed_head, |head, inbound| Self::mqc_link(head, inbound));
+		head == Self::dmq_mqc_head(para)
+	}
+
+	/// Returns the MQC head that results from appending the given message to a chain with the
+	/// head `prev`.
+	pub(crate) fn mqc_link(prev: Hash, inbound: &InboundDownwardMessage<T::BlockNumber>) -> Hash {
+		Self::mqc_link_raw(prev, inbound.sent_at, T::Hashing::hash_of(&inbound.msg))
+	}
+
+	/// Same as [`Self::mqc_link`], for callers that already have the hash of the message.
+	pub(crate) fn mqc_link_raw(prev: Hash, sent_at: T::BlockNumber, msg_hash: T::Hash) -> Hash {
+		BlakeTwo256::hash_of(&(prev, sent_at, msg_hash))
+	}
+
 	/// Returns the number of pending downward messages addressed to the given para.
 	///
 	/// Returns 0 if the para doesn't have an associated downward message queue.
@@ -309,6 +852,65 @@ impl<T: Config> Pallet<T> {
 			.saturated_into::<u32>()
 	}
 
+	/// Returns the total size in bytes of the pending downward messages addressed to the given para.
+	///
+	/// Returns 0 if the para doesn't have an associated downward message queue.
+	pub fn dmq_total_bytes(para: ParaId) -> u64 {
+		DownwardMessageQueueBytes::<T>::get(&para)
+	}
+
+	/// Returns a summary of the downward message queue of the given para.
+	///
+	/// This doesn't decode the queue, so it is cheap enough to be polled for every para.
+	pub fn dmq_stats(para: ParaId) -> DmqStats<T::BlockNumber> {
+		DmqStats {
+			message_count: Self::dmq_length(para),
+			total_bytes: Self::dmq_total_bytes(para),
+			oldest_sent_at: Self::dmq_oldest_sent_at(para),
+		}
+	}
+
+	/// Returns how many more messages and bytes can be queued for the given para before
+	/// `queue_downward_message` starts rejecting messages.
+	///
+	/// No capacity is left for a para that is being offboarded if
+	/// `dmp_reject_offboarding_recipients` is set.
+	pub fn dmq_remaining_capacity(
+		config: &HostConfiguration<T::BlockNumber>,
+		para: ParaId,
+	) -> DmqCapacity {
+		if config.dmp_reject_offboarding_recipients &&
+			paras::Pallet::<T>::lifecycle(para).map_or(false, |l| l.is_offboarding())
+		{
+			return DmqCapacity::default()
+		}
+
+		// `can_queue_downward_message` accepts messages as long as the queue length doesn't
+		// exceed the hard limit, so one more message than the hard limit fits.
+		let max_length = Self::dmq_max_length(config.max_downward_message_size)
+			.saturating_add(1)
+			.min(T::MaxPendingDownwardMessages::get());
+		DmqCapacity {
+			messages_free: max_length.saturating_sub(Self::dmq_length(para)) as u64,
+			bytes_free: (config.max_downward_queue_bytes as u64)
+				.saturating_sub(Self::dmq_total_bytes(para)),
+		}
+	}
+
+	/// Returns the block number at which the oldest pending downward message addressed to the
+	/// given para was sent.
+	fn dmq_oldest_sent_at(para: ParaId) -> Option<T::BlockNumber> {
+		DownwardMessageQueueOldestSentAt::<T>::get(&para)
+	}
+
+	/// Returns an iterator over the paras that have pending downward messages.
+	///
+	/// Paras with an empty downward message queue are skipped, even if the queue is still present
+	/// in storage.
+	pub fn paras_with_pending_dmq() -> impl Iterator<Item = ParaId> {
+		DownwardMessageQueues::<T>::iter_keys().filter(|para| Self::dmq_length(*para) > 0)
+	}
+
 	fn dmq_max_length(max_downward_message_size: u32) -> u32 {
 		MAX_POSSIBLE_ALLOCATION.checked_div(max_downward_message_size).unwrap_or(0)
 	}
@@ -320,6 +922,20 @@ impl<T: Config> Pallet<T> {
 		DownwardMessageQueues::<T>::get(&recipient)
 	}
 
+	/// Returns up to `max_messages` of the most recent downward messages for the given para.
+	///
+	/// Unlike [`Self::dmq_contents`], the newest message comes first.
+	pub fn dmq_contents_recent(
+		recipient: ParaId,
+		max_messages: u32,
+	) -> Vec<InboundDownwardMessage<T::BlockNumber>> {
+		DownwardMessageQueues::<T>::get(&recipient)
+			.into_iter()
+			.rev()
+			.take(max_messages as usize)
+			.collect()
+	}
+
 	/// Raise the delivery fee factor by a multiplicative factor and stores the resulting value.
 	///
 	/// Returns the new delivery fee factor after the increment.
@@ -343,6 +959,104 @@ impl<T: Config> Pallet<T> {
 	}
 }
 
+#[cfg(feature = "runtime-benchmarks")]
+impl<T: Config> Pallet<T> {
+	/// Append `count` messages of `msg_size` bytes to the downward message queue of `para`.
+	///
+	/// Storage is left in the same state as after `count` calls to `queue_downward_message`,
+	/// including the MQC head and the queue size, but the queue limits and the delivery fee factor
+	/// are ignored. This is meant for setting up worst-case benchmarks cheaply.
+	pub fn fill_dmq_for_benchmarks(para: ParaId, count: u32, msg_size: u32) {
+		let inbound = InboundDownwardMessage {
+			msg: vec![0u8; msg_size as usize],
+			sent_at: frame_system::Pallet::<T>::block_number(),
+		};
+		let msg_hash = T::Hashing::hash_of(&inbound.msg);
+
+		DownwardMessageQueueHeads::<T>::mutate(para, |head| {
+			if head.is_zero() {
+				LastPrunedMqcHead::<T>::insert(para, Hash::zero());
+			}
+			for _ in 0..count {
+				*head = Self::mqc_link_raw(*head, inbound.sent_at, msg_hash);
+			}
+		});
+		DownwardMessageQueues::<T>::mutate(para, |queue| {
+			if queue.is_empty() && count > 0 {
+				DownwardMessageQueueOldestSentAt::<T>::insert(para, inbound.sent_at);
+			}
+			queue.extend((0..count).map(|_| inbound.clone()));
+		});
+		DownwardMessageQueueBytes::<T>::mutate(para, |bytes| {
+			*bytes = bytes.saturating_add(msg_size as u64 * count as u64);
+		});
+	}
+}
+
+#[cfg(any(feature = "try-runtime", test))]
+impl<T: Config> Pallet<T> {
+	/// Checks that the storage items of this pallet are consistent with each other.
+	///
+	/// The para whose storage is inconsistent is logged along with the returned error.
+	pub(crate) fn do_try_state() -> Result<(), &'static str> {
+		for (para, queue) in DownwardMessageQueues::<T>::iter() {
+			let bytes = queue.iter().map(|m| m.msg.len() as u64).sum::<u64>();
+			Self::ensure_consistent(
+				para,
+				bytes == DownwardMessageQueueBytes::<T>::get(&para),
+				"DownwardMessageQueueBytes doesn't match the size of the queue",
+			)?;
+			Self::ensure_consistent(
+				para,
+				queue.windows(2).all(|w| w[0].sent_at <= w[1].sent_at),
+				"Downward messages are not ordered by the block they were sent at",
+			)?;
+			Self::ensure_consistent(
+				para,
+				queue.first().map(|m| m.sent_at) ==
+					DownwardMessageQueueOldestSentAt::<T>::get(&para),
+				"DownwardMessageQueueOldestSentAt doesn't match the oldest message of the queue",
+			)?;
+			Self::ensure_consistent(
+				para,
+				queue.is_empty() || !DownwardMessageQueueHeads::<T>::get(&para).is_zero(),
+				"Non-empty downward message queue has no MQC head",
+			)?;
+			Self::ensure_consistent(
+				para,
+				LastPrunedMqcHead::<T>::get(&para)
+					.map_or(true, |head| Self::verify_mqc_continuity(para, head)),
+				"Pending downward messages don't continue the MQC from the last pruned head",
+			)?;
+		}
+
+		for para in DownwardMessageQueueBytes::<T>::iter_keys() {
+			Self::ensure_consistent(
+				para,
+				DownwardMessageQueues::<T>::contains_key(&para),
+				"DownwardMessageQueueBytes is set for a para without a downward message queue",
+			)?;
+		}
+		for para in DownwardMessageQueueOldestSentAt::<T>::iter_keys() {
+			Self::ensure_consistent(
+				para,
+				DownwardMessageQueues::<T>::contains_key(&para),
+				"DownwardMessageQueueOldestSentAt is set for a para without a downward message queue",
+			)?;
+		}
+
+		Ok(())
+	}
+
+	fn ensure_consistent(para: ParaId, check: bool, err: &'static str) -> Result<(), &'static str> {
+		if !check {
+			log::error!(target: LOG_TARGET, "try_state failed para={}: {}", u32::from(para), err);
+			return Err(err)
+		}
+		Ok(())
+	}
+}
+
 impl<T: Config> FeeTracker for Pallet<T> {
 	fn get_fee_factor(para: ParaId) -> FixedU128 {
 		DeliveryFeeFactor::<T>::get(para)
@@ -0,0 +1,117 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{Pallet as Dmp, *};
+use frame_system::RawOrigin;
+
+/// The maximum number of messages the downward message queue is filled with.
+///
+/// This is the hard limit of the queue when `max_downward_message_size` is set to 1 MiB, as it is
+/// on the production networks.
+const MAX_DMQ_LENGTH: u32 = 32;
+
+/// The maximum size of a downward message, as it is configured on the production networks.
+const MAX_DOWNWARD_MESSAGE_SIZE: u32 = 1024 * 1024;
+
+// Fill the downward message queue of `para` with `count` messages of the maximum allowed size.
+fn fill_dmq<T: Config>(para: ParaId, count: u32) {
+	let config = configuration::ActiveConfig::<T>::get();
+	Dmp::<T>::fill_dmq_for_benchmarks(para, count, config.max_downward_message_size);
+}
+
+frame_benchmarking::benchmarks! {
+	prune_dmq {
+		let m in 1 .. MAX_DMQ_LENGTH;
+		let para = ParaId::from(1000);
+		fill_dmq::<T>(para, m);
+		// Keeping the newest message means that all the pruned messages have to be hashed.
+	}: { Dmp::<T>::prune_dmq(para, m - 1); }
+	verify {
+		assert_eq!(Dmp::<T>::dmq_length(para), 1);
+	}
+
+	clean_dmp_after_outgoing {
+		let para = ParaId::from(1000);
+		fill_dmq::<T>(para, MAX_DMQ_LENGTH);
+	}: { Dmp::<T>::clean_dmp_after_outgoing(&para); }
+	verify {
+		assert_eq!(Dmp::<T>::dmq_length(para), 0);
+		assert!(Dmp::<T>::dmq_mqc_head(para).is_zero());
+	}
+
+	force_prune_dmq {
+		let m in 1 .. MAX_DMQ_LENGTH;
+		let para = ParaId::from(1000);
+		fill_dmq::<T>(para, m);
+		// Keeping the newest message means that all the pruned messages have to be hashed.
+	}: _(RawOrigin::Root, para, m - 1, m)
+	verify {
+		assert_eq!(Dmp::<T>::dmq_length(para), 1);
+		assert!(!Dmp::<T>::dmq_mqc_head(para).is_zero());
+	}
+
+	queue_downward_message {
+		let s in 1 .. MAX_DOWNWARD_MESSAGE_SIZE;
+		let para = ParaId::from(1000);
+		configuration::ActiveConfig::<T>::mutate(|config| {
+			config.max_downward_message_size = MAX_DOWNWARD_MESSAGE_SIZE;
+			config.max_downward_queue_bytes = MAX_POSSIBLE_ALLOCATION;
+		});
+		let config = configuration::ActiveConfig::<T>::get();
+		// The message is appended without decoding the queue, but the encoded queue is still read
+		// from storage to append to it, so leave room for just one more message.
+		fill_dmq::<T>(para, MAX_DMQ_LENGTH - 1);
+	}: {
+		Dmp::<T>::queue_downward_message(&config, para, vec![0u8; s as usize])
+			.expect("the queue has room for one more message; qed");
+	}
+	verify {
+		assert_eq!(Dmp::<T>::dmq_length(para), MAX_DMQ_LENGTH);
+	}
+
+	initializer_initialize {
+		let p in 0 .. MAX_EXPIRY_CHECKS_PER_BLOCK;
+		configuration::ActiveConfig::<T>::mutate(|config| {
+			config.downward_message_ttl = 1u32.into();
+		});
+		// Every queue is checked, but none of the messages has expired yet. Dropping expired
+		// messages is accounted for separately.
+		for para in 0..p {
+			fill_dmq::<T>(ParaId::from(para), 1);
+		}
+		let now = frame_system::Pallet::<T>::block_number();
+	}: { Dmp::<T>::initializer_initialize(now); }
+	verify {
+		for para in 0..p {
+			assert_eq!(Dmp::<T>::dmq_length(ParaId::from(para)), 1);
+		}
+	}
+
+	sweep_dangling_dmq {
+		let para = ParaId::from(1000);
+		fill_dmq::<T>(para, MAX_DMQ_LENGTH);
+	}: _(RawOrigin::Root, para)
+	verify {
+		assert_eq!(Dmp::<T>::dmq_length(para), 0);
+		assert!(Dmp::<T>::dmq_mqc_head(para).is_zero());
+	}
+}
+
+frame_benchmarking::impl_benchmark_test_suite!(
+	Dmp,
+	crate::mock::new_test_ext(crate::dmp::tests::default_genesis_config()),
+	crate::mock::Test
+);
@@ -0,0 +1,156 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::dmp::{
+	Config, DownwardMessageQueueBytes, DownwardMessageQueueOldestSentAt, DownwardMessageQueues,
+	Pallet, LOG_TARGET,
+};
+use frame_support::{
+	pallet_prelude::*,
+	traits::{OnRuntimeUpgrade, StorageVersion},
+	weights::Weight,
+};
+
+pub const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
+pub mod v1 {
+	use super::*;
+	#[cfg(feature = "try-runtime")]
+	use primitives::Id as ParaId;
+	#[cfg(feature = "try-runtime")]
+	use sp_std::prelude::*;
+
+	/// Backfills `DownwardMessageQueueBytes` and `DownwardMessageQueueOldestSentAt` from the contents
+	/// of the existing queues.
+	pub struct MigrateToV1<T>(sp_std::marker::PhantomData<T>);
+	impl<T: Config> OnRuntimeUpgrade for MigrateToV1<T> {
+		#[cfg(feature = "try-runtime")]
+		fn pre_upgrade() -> Result<Vec<u8>, &'static str> {
+			log::trace!(target: LOG_TARGET, "Running pre_upgrade()");
+
+			ensure!(StorageVersion::get::<Pallet<T>>() == 0, "The migration requires version 0");
+			let queues = DownwardMessageQueues::<T>::iter()
+				.map(|(para, queue)| {
+					let bytes = queue.iter().map(|m| m.msg.len() as u64).sum::<u64>();
+					(para, bytes, queue.first().map(|m| m.sent_at))
+				})
+				.collect::<Vec<_>>();
+			Ok(queues.encode())
+		}
+
+		fn on_runtime_upgrade() -> Weight {
+			if StorageVersion::get::<Pallet<T>>() == 0 {
+				let mut weight = T::DbWeight::get().reads(1);
+
+				let (mut queues, mut writes, mut read_bytes) = (0u64, 0u64, 0u64);
+				for (para, queue) in DownwardMessageQueues::<T>::iter() {
+					let bytes = queue.iter().map(|m| m.msg.len() as u64).sum::<u64>();
+					if bytes > 0 {
+						DownwardMessageQueueBytes::<T>::insert(para, bytes);
+						writes += 1;
+					}
+					if let Some(oldest) = queue.first() {
+						DownwardMessageQueueOldestSentAt::<T>::insert(para, oldest.sent_at);
+						writes += 1;
+					}
+					queues += 1;
+					read_bytes = read_bytes.saturating_add(queue.encoded_size() as u64);
+				}
+				log::info!(target: LOG_TARGET, "Backfilled {} downward message queues", queues);
+
+				// every queue is decoded, so the proof contains all of them.
+				weight.saturating_accrue(T::DbWeight::get().reads_writes(queues, writes));
+				weight.saturating_accrue(Weight::from_parts(0, read_bytes));
+
+				StorageVersion::new(1).put::<Pallet<T>>();
+
+				weight.saturating_add(T::DbWeight::get().writes(1))
+			} else {
+				log::warn!(target: LOG_TARGET, "skipping v1, should be removed");
+				T::DbWeight::get().reads(1)
+			}
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn post_upgrade(state: Vec<u8>) -> Result<(), &'static str> {
+			log::trace!(target: LOG_TARGET, "Running post_upgrade()");
+
+			ensure!(
+				StorageVersion::get::<Pallet<T>>() == STORAGE_VERSION,
+				"Storage version should be `1` after the migration"
+			);
+			let queues = Vec::<(ParaId, u64, Option<T::BlockNumber>)>::decode(&mut &state[..])
+				.map_err(|_| "Cannot decode the summaries of the queues")?;
+			for (para, bytes, oldest_sent_at) in &queues {
+				ensure!(
+					DownwardMessageQueueBytes::<T>::get(para) == *bytes,
+					"DownwardMessageQueueBytes doesn't match the size of the queue"
+				);
+				ensure!(
+					DownwardMessageQueueOldestSentAt::<T>::get(para) == *oldest_sent_at,
+					"DownwardMessageQueueOldestSentAt doesn't match the oldest message of the queue"
+				);
+			}
+			ensure!(
+				DownwardMessageQueueBytes::<T>::iter_keys().count() ==
+					queues.iter().filter(|(_, bytes, _)| *bytes > 0).count(),
+				"DownwardMessageQueueBytes is set for a para without pending messages"
+			);
+			ensure!(
+				DownwardMessageQueueOldestSentAt::<T>::iter_keys().count() ==
+					queues
+						.iter()
+						.filter(|(_, _, oldest_sent_at)| oldest_sent_at.is_some())
+						.count(),
+				"DownwardMessageQueueOldestSentAt is set for a para without pending messages"
+			);
+			Ok(())
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::mock::{new_test_ext, Test};
+	use primitives::{Id as ParaId, InboundDownwardMessage};
+
+	#[test]
+	fn migrate_to_v1_backfills_queue_summaries() {
+		let a = ParaId::from(1312);
+		let b = ParaId::from(228);
+
+		new_test_ext(Default::default()).execute_with(|| {
+			StorageVersion::new(0).put::<Pallet<Test>>();
+			DownwardMessageQueues::<Test>::insert(
+				a,
+				vec![
+					InboundDownwardMessage { msg: vec![1, 2, 3], sent_at: 1 },
+					InboundDownwardMessage { msg: vec![4, 5], sent_at: 2 },
+				],
+			);
+			DownwardMessageQueues::<Test>::insert(b, Vec::<InboundDownwardMessage<_>>::new());
+
+			v1::MigrateToV1::<Test>::on_runtime_upgrade();
+
+			assert_eq!(DownwardMessageQueueBytes::<Test>::get(a), 5);
+			assert!(!DownwardMessageQueueBytes::<Test>::contains_key(b));
+			assert_eq!(DownwardMessageQueueOldestSentAt::<Test>::get(a), Some(1));
+			assert!(!DownwardMessageQueueOldestSentAt::<Test>::contains_key(b));
+			assert_eq!(StorageVersion::get::<Pallet<Test>>(), STORAGE_VERSION);
+		});
+	}
+}
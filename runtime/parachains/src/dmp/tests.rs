@@ -17,12 +17,16 @@
 use super::*;
 use crate::{
 	configuration::ActiveConfig,
-	mock::{new_test_ext, Configuration, Dmp, MockGenesisConfig, Paras, System, Test},
+	mock::{
+		assert_last_event, new_test_ext, Configuration, Dmp, MaxPendingDownwardMessages,
+		MockGenesisConfig, Paras, ParasPendingAvailability, RuntimeOrigin, System, Test,
+	},
 };
-use frame_support::assert_ok;
+use frame_support::{assert_noop, assert_ok};
 use hex_literal::hex;
 use parity_scale_codec::Encode;
 use primitives::BlockNumber;
+use sp_runtime::traits::BadOrigin;
 
 pub(crate) fn run_to_block(to: BlockNumber, new_session: Option<Vec<BlockNumber>>) {
 	while System::block_number() < to {
@@ -42,7 +46,7 @@ pub(crate) fn run_to_block(to: BlockNumber, new_session: Option<Vec<BlockNumber>
 	}
 }
 
-fn default_genesis_config() -> MockGenesisConfig {
+pub(super) fn default_genesis_config() -> MockGenesisConfig {
 	MockGenesisConfig {
 		configuration: crate::configuration::GenesisConfig {
 			config: crate::configuration::HostConfiguration {
@@ -80,6 +84,9 @@ fn clean_dmp_works() {
 		assert!(DownwardMessageQueues::<Test>::get(&a).is_empty());
 		assert!(DownwardMessageQueues::<Test>::get(&b).is_empty());
 		assert!(!DownwardMessageQueues::<Test>::get(&c).is_empty());
+		assert_eq!(Dmp::dmq_total_bytes(a), 0);
+		assert_eq!(Dmp::dmq_total_bytes(b), 0);
+		assert_eq!(Dmp::dmq_total_bytes(c), 3);
 	});
 }
 
@@ -120,26 +127,243 @@ fn dmp_mqc_head_fixture() {
 	});
 }
 
+#[test]
+fn queued_messages_are_stored_as_encoded_vec() {
+	let a = ParaId::from(1312);
+
+	new_test_ext(default_genesis_config()).execute_with(|| {
+		run_to_block(2, None);
+		queue_downward_message(a, vec![1, 2, 3]).unwrap();
+		run_to_block(3, None);
+		queue_downward_message(a, vec![4; 300]).unwrap();
+
+		// the raw value is the same as if the whole queue was written at once.
+		let expected = vec![
+			InboundDownwardMessage { sent_at: 2, msg: vec![1, 2, 3] },
+			InboundDownwardMessage { sent_at: 3, msg: vec![4; 300] },
+		];
+		assert_eq!(
+			sp_io::storage::get(&DownwardMessageQueues::<Test>::hashed_key_for(a)),
+			Some(expected.encode().into()),
+		);
+		assert_eq!(Dmp::dmq_length(a), 2);
+
+		// appending after the queue was pruned to empty.
+		Dmp::prune_dmq(a, 2);
+		queue_downward_message(a, vec![5]).unwrap();
+		assert_eq!(Dmp::dmq_contents(a), vec![InboundDownwardMessage { sent_at: 3, msg: vec![5] }]);
+	});
+}
+
+#[test]
+fn mqc_link_helpers_agree() {
+	new_test_ext(default_genesis_config()).execute_with(|| {
+		let prev = Hash::repeat_byte(1);
+		let inbound = InboundDownwardMessage { sent_at: 7, msg: vec![1, 2, 3] };
+		let msg_hash = <Test as frame_system::Config>::Hashing::hash_of(&inbound.msg);
+
+		assert_eq!(Dmp::mqc_link(prev, &inbound), Dmp::mqc_link_raw(prev, 7, msg_hash));
+		assert_eq!(
+			Dmp::mqc_link(prev, &inbound),
+			BlakeTwo256::hash_of(&(prev, 7 as BlockNumber, msg_hash))
+		);
+	});
+}
+
+#[test]
+fn verify_mqc_continuity_works() {
+	let a = ParaId::from(1312);
+
+	new_test_ext(default_genesis_config()).execute_with(|| {
+		// an empty queue continues from the genesis head.
+		assert!(Dmp::verify_mqc_continuity(a, Hash::zero()));
+
+		queue_downward_message(a, vec![1, 2, 3]).unwrap();
+		let head_after_first = Dmp::dmq_mqc_head(a);
+		queue_downward_message(a, vec![4, 5, 6]).unwrap();
+		queue_downward_message(a, vec![7, 8, 9]).unwrap();
+		assert!(Dmp::verify_mqc_continuity(a, Hash::zero()));
+
+		// the para processed the first message.
+		Dmp::prune_dmq(a, 1);
+		assert!(Dmp::verify_mqc_continuity(a, head_after_first));
+		assert!(!Dmp::verify_mqc_continuity(a, Hash::zero()));
+		assert!(!Dmp::verify_mqc_continuity(a, Dmp::dmq_mqc_head(a)));
+
+		// once the queue is drained the latest head is the only valid one.
+		Dmp::prune_dmq(a, 2);
+		assert!(Dmp::verify_mqc_continuity(a, Dmp::dmq_mqc_head(a)));
+		assert!(!Dmp::verify_mqc_continuity(a, head_after_first));
+	});
+}
+
+#[test]
+fn last_pruned_mqc_head_is_tracked() {
+	let a = ParaId::from(1312);
+
+	new_test_ext(default_genesis_config()).execute_with(|| {
+		queue_downward_message(a, vec![1, 2, 3]).unwrap();
+		let head_after_first = Dmp::dmq_mqc_head(a);
+		queue_downward_message(a, vec![4, 5, 6]).unwrap();
+		queue_downward_message(a, vec![7, 8, 9]).unwrap();
+		// no message was removed from the chain yet.
+		assert_eq!(Dmp::dmq_last_pruned_mqc_head(a), Some(Hash::zero()));
+		assert_ok!(Dmp::do_try_state());
+
+		Dmp::prune_dmq(a, 1);
+		assert_eq!(Dmp::dmq_last_pruned_mqc_head(a), Some(head_after_first));
+		assert!(Dmp::verify_mqc_continuity(a, head_after_first));
+
+		// pruning nothing keeps the head.
+		Dmp::prune_dmq(a, 0);
+		assert_eq!(Dmp::dmq_last_pruned_mqc_head(a), Some(head_after_first));
+
+		// once the queue is drained it's the MQC head.
+		Dmp::prune_dmq(a, 2);
+		assert_eq!(Dmp::dmq_last_pruned_mqc_head(a), Some(Dmp::dmq_mqc_head(a)));
+		assert_ok!(Dmp::do_try_state());
+
+		// a head that the pending messages don't continue from is caught.
+		queue_downward_message(a, vec![10]).unwrap();
+		assert_ok!(Dmp::do_try_state());
+		LastPrunedMqcHead::<Test>::insert(a, head_after_first);
+		assert!(Dmp::do_try_state().is_err());
+
+		Dmp::clean_dmp_after_outgoing(&a);
+		assert_eq!(Dmp::dmq_last_pruned_mqc_head(a), None);
+
+		// the chain starts over.
+		queue_downward_message(a, vec![1, 2, 3]).unwrap();
+		assert_eq!(Dmp::dmq_last_pruned_mqc_head(a), Some(Hash::zero()));
+	});
+}
+
+#[test]
+fn unknown_last_pruned_mqc_head_is_not_guessed() {
+	let a = ParaId::from(1312);
+
+	new_test_ext(default_genesis_config()).execute_with(|| {
+		queue_downward_message(a, vec![1, 2, 3]).unwrap();
+		queue_downward_message(a, vec![4, 5, 6]).unwrap();
+		queue_downward_message(a, vec![7, 8, 9]).unwrap();
+		queue_downward_message(a, vec![10]).unwrap();
+		// like a queue that had messages removed before the head started being tracked: the first
+		// pending message isn't linked to the zero hash.
+		Dmp::prune_dmq(a, 1);
+		LastPrunedMqcHead::<Test>::remove(a);
+		assert!(!Dmp::verify_mqc_continuity(a, Hash::zero()));
+
+		// removing a part of the queue keeps the head unknown.
+		Dmp::prune_dmq(a, 1);
+		assert_eq!(Dmp::dmq_last_pruned_mqc_head(a), None);
+		assert_ok!(Dmp::do_try_state());
+
+		// the head is known again once the queue is drained.
+		Dmp::prune_dmq(a, 2);
+		assert_eq!(Dmp::dmq_last_pruned_mqc_head(a), Some(Dmp::dmq_mqc_head(a)));
+		assert_ok!(Dmp::do_try_state());
+
+		queue_downward_message(a, vec![11]).unwrap();
+		Dmp::prune_dmq(a, 1);
+		assert_eq!(Dmp::dmq_last_pruned_mqc_head(a), Some(Dmp::dmq_mqc_head(a)));
+		assert_ok!(Dmp::do_try_state());
+	});
+}
+
 #[test]
 fn check_processed_downward_messages() {
 	let a = ParaId::from(1312);
 
 	new_test_ext(default_genesis_config()).execute_with(|| {
+		let config = Configuration::config();
+
 		// processed_downward_messages=0 is allowed when the DMQ is empty.
-		assert!(Dmp::check_processed_downward_messages(a, 0).is_ok());
+		assert!(Dmp::check_processed_downward_messages(&config, a, 0).is_ok());
 
 		queue_downward_message(a, vec![1, 2, 3]).unwrap();
 		queue_downward_message(a, vec![4, 5, 6]).unwrap();
 		queue_downward_message(a, vec![7, 8, 9]).unwrap();
 
 		// 0 doesn't pass if the DMQ has msgs.
-		assert!(!Dmp::check_processed_downward_messages(a, 0).is_ok());
+		assert!(!Dmp::check_processed_downward_messages(&config, a, 0).is_ok());
 		// a candidate can consume up to 3 messages
-		assert!(Dmp::check_processed_downward_messages(a, 1).is_ok());
-		assert!(Dmp::check_processed_downward_messages(a, 2).is_ok());
-		assert!(Dmp::check_processed_downward_messages(a, 3).is_ok());
+		assert!(Dmp::check_processed_downward_messages(&config, a, 1).is_ok());
+		assert!(Dmp::check_processed_downward_messages(&config, a, 2).is_ok());
+		assert!(Dmp::check_processed_downward_messages(&config, a, 3).is_ok());
 		// there is no 4 messages in the queue
-		assert!(!Dmp::check_processed_downward_messages(a, 4).is_ok());
+		assert!(!Dmp::check_processed_downward_messages(&config, a, 4).is_ok());
+	});
+}
+
+#[test]
+fn processed_downward_messages_acceptance_err_carries_para() {
+	let a = ParaId::from(1312);
+
+	new_test_ext(default_genesis_config()).execute_with(|| {
+		let config = Configuration::config();
+		queue_downward_message(a, vec![1, 2, 3]).unwrap();
+
+		let err = Dmp::check_processed_downward_messages(&config, a, 2).unwrap_err();
+		assert_eq!(
+			err,
+			ProcessedDownwardMessagesAcceptanceErr::Underflow {
+				para: a,
+				processed_downward_messages: 2,
+				dmq_length: 1,
+			}
+		);
+		assert_eq!(
+			err.to_string(),
+			"processed_downward_messages = 2 for para 1312, but dmq_length is only 1"
+		);
+		assert_eq!(
+			ProcessedDownwardMessagesAcceptanceErr::decode(&mut &err.encode()[..]).unwrap(),
+			err
+		);
+	});
+}
+
+#[test]
+fn check_processed_downward_messages_respects_configured_minimum() {
+	let a = ParaId::from(1312);
+
+	new_test_ext(default_genesis_config()).execute_with(|| {
+		let mut config = Configuration::config();
+
+		queue_downward_message(a, vec![1, 2, 3]).unwrap();
+		queue_downward_message(a, vec![4, 5, 6]).unwrap();
+		queue_downward_message(a, vec![7, 8, 9]).unwrap();
+
+		// a candidate has to consume at least 2 messages.
+		config.dmp_advancement_rule_min_processed = 2;
+		assert_eq!(
+			Dmp::check_processed_downward_messages(&config, a, 0),
+			Err(ProcessedDownwardMessagesAcceptanceErr::AdvancementRule { para: a })
+		);
+		assert_eq!(
+			Dmp::check_processed_downward_messages(&config, a, 1),
+			Err(ProcessedDownwardMessagesAcceptanceErr::AdvancementRuleMinimum {
+				para: a,
+				processed_downward_messages: 1,
+				required: 2,
+			})
+		);
+		assert!(Dmp::check_processed_downward_messages(&config, a, 2).is_ok());
+		assert!(Dmp::check_processed_downward_messages(&config, a, 3).is_ok());
+
+		// the minimum is capped by the number of pending messages.
+		config.dmp_advancement_rule_min_processed = 5;
+		assert!(!Dmp::check_processed_downward_messages(&config, a, 2).is_ok());
+		assert!(Dmp::check_processed_downward_messages(&config, a, 3).is_ok());
+
+		// with the lowest allowed minimum a single message is enough.
+		config.dmp_advancement_rule_min_processed = 1;
+		assert_eq!(
+			Dmp::check_processed_downward_messages(&config, a, 0),
+			Err(ProcessedDownwardMessagesAcceptanceErr::AdvancementRule { para: a })
+		);
+		assert!(Dmp::check_processed_downward_messages(&config, a, 1).is_ok());
+		assert!(!Dmp::check_processed_downward_messages(&config, a, 4).is_ok());
 	});
 }
 
@@ -148,6 +372,8 @@ fn dmq_pruning() {
 	let a = ParaId::from(1312);
 
 	new_test_ext(default_genesis_config()).execute_with(|| {
+		// Events are not deposited in the genesis block.
+		System::set_block_number(1);
 		assert_eq!(Dmp::dmq_length(a), 0);
 
 		queue_downward_message(a, vec![1, 2, 3]).unwrap();
@@ -158,9 +384,671 @@ fn dmq_pruning() {
 		// pruning 0 elements shouldn't change anything.
 		Dmp::prune_dmq(a, 0);
 		assert_eq!(Dmp::dmq_length(a), 3);
+		assert!(System::events().is_empty());
 
 		Dmp::prune_dmq(a, 2);
 		assert_eq!(Dmp::dmq_length(a), 1);
+		assert_last_event(Event::DownwardMessagesProcessed(a, 2, 1).into());
+
+		Dmp::prune_dmq(a, 1);
+		assert_eq!(Dmp::dmq_length(a), 0);
+		assert_last_event(Event::DownwardMessagesProcessed(a, 1, 0).into());
+
+		// pruning an empty queue doesn't emit an event.
+		let events = System::events().len();
+		Dmp::prune_dmq(a, 1);
+		assert_eq!(System::events().len(), events);
+	});
+}
+
+#[test]
+fn dmq_pruning_at_queue_boundary() {
+	let a = ParaId::from(1312);
+
+	let fill = || {
+		for i in 0..4u8 {
+			queue_downward_message(a, vec![i; 4]).unwrap();
+		}
+	};
+
+	new_test_ext(default_genesis_config()).execute_with(|| {
+		// one short of the queue length leaves the last message.
+		fill();
+		Dmp::prune_dmq(a, 3);
+		assert_eq!(
+			Dmp::dmq_contents(a).iter().map(|m| m.msg.clone()).collect::<Vec<_>>(),
+			vec![vec![3; 4]]
+		);
+		assert_eq!(Dmp::dmq_total_bytes(a), 4);
+		assert_ok!(Dmp::do_try_state());
+
+		// exactly the queue length empties the queue.
+		Dmp::prune_dmq(a, 1);
+		fill();
+		Dmp::prune_dmq(a, 4);
+		assert_eq!(Dmp::dmq_length(a), 0);
+		assert_eq!(Dmp::dmq_total_bytes(a), 0);
+		assert!(!DownwardMessageQueueBytes::<Test>::contains_key(&a));
+		assert_ok!(Dmp::do_try_state());
+
+		// more than the queue length is treated as pruning everything.
+		fill();
+		Dmp::prune_dmq(a, 5);
+		assert_eq!(Dmp::dmq_length(a), 0);
+		assert_eq!(Dmp::dmq_total_bytes(a), 0);
+		assert_ok!(Dmp::do_try_state());
+
+		// the queue is usable after being emptied.
+		queue_downward_message(a, vec![1]).unwrap();
+		assert_eq!(Dmp::dmq_length(a), 1);
+		assert_eq!(Dmp::dmq_total_bytes(a), 1);
+		assert_ok!(Dmp::do_try_state());
+	});
+}
+
+#[test]
+fn random_enqueue_and_prune_interleavings_stay_consistent() {
+	use std::collections::{BTreeMap, VecDeque};
+
+	const MAX_QUEUE_BYTES: u64 = 64;
+	let paras = [ParaId::from(1), ParaId::from(2), ParaId::from(3)];
+
+	// xorshift, so that a failing sequence can be replayed from the seed.
+	let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+	let mut next = |bound: u64| {
+		seed ^= seed << 13;
+		seed ^= seed >> 7;
+		seed ^= seed << 17;
+		seed % bound
+	};
+
+	let mut genesis = default_genesis_config();
+	genesis.configuration.config.max_downward_queue_bytes = MAX_QUEUE_BYTES as u32;
+
+	new_test_ext(genesis).execute_with(|| {
+		System::set_block_number(1);
+		let mut queues = BTreeMap::<ParaId, VecDeque<InboundDownwardMessage<BlockNumber>>>::new();
+		let mut heads = BTreeMap::<ParaId, Hash>::new();
+
+		for step in 0..1000 {
+			let para = paras[next(paras.len() as u64) as usize];
+			let queue = queues.entry(para).or_default();
+			let bytes = queue.iter().map(|m| m.msg.len() as u64).sum::<u64>();
+
+			match next(4) {
+				0 | 1 => {
+					let msg = vec![step as u8; 1 + next(16) as usize];
+					let fits = bytes + msg.len() as u64 <= MAX_QUEUE_BYTES;
+					let inbound = InboundDownwardMessage {
+						sent_at: System::block_number(),
+						msg: msg.clone(),
+					};
+
+					assert_eq!(queue_downward_message(para, msg).is_ok(), fits, "step {}", step);
+					if fits {
+						let head = heads.entry(para).or_default();
+						*head = Dmp::mqc_link(*head, &inbound);
+						queue.push_back(inbound);
+					}
+				},
+				2 => {
+					let count = next(queue.len() as u64 + 2) as u32;
+					Dmp::prune_dmq(para, count);
+					queue.drain(..(count as usize).min(queue.len()));
+				},
+				_ => System::set_block_number(System::block_number() + 1),
+			}
+
+			assert_eq!(Dmp::dmq_contents(para), Vec::from(queue.clone()), "step {}", step);
+			assert_eq!(
+				Dmp::dmq_total_bytes(para),
+				queue.iter().map(|m| m.msg.len() as u64).sum::<u64>(),
+				"step {}",
+				step
+			);
+			assert_eq!(
+				Dmp::dmq_mqc_head(para),
+				heads.get(&para).copied().unwrap_or_default(),
+				"step {}",
+				step
+			);
+			assert_ok!(Dmp::do_try_state());
+		}
+	});
+}
+
+#[test]
+fn force_prune_dmq_works() {
+	let a = ParaId::from(1312);
+
+	new_test_ext(default_genesis_config()).execute_with(|| {
+		System::set_block_number(1);
+		queue_downward_message(a, vec![1, 2, 3]).unwrap();
+		queue_downward_message(a, vec![4, 5, 6]).unwrap();
+		queue_downward_message(a, vec![7, 8, 9]).unwrap();
+		let head = Dmp::dmq_mqc_head(a);
+
+		// only root can prune the queue.
+		assert_noop!(Dmp::force_prune_dmq(RuntimeOrigin::signed(1), a, 1, 3), BadOrigin);
+		// the witness must cover the whole queue.
+		assert_noop!(
+			Dmp::force_prune_dmq(RuntimeOrigin::root(), a, 1, 2),
+			Error::<Test>::WrongQueueLengthWitness
+		);
+		// can't prune more messages than there are in the queue.
+		assert_noop!(
+			Dmp::force_prune_dmq(RuntimeOrigin::root(), a, 4, 3),
+			Error::<Test>::PruneCountExceedsQueueLength
+		);
+		// a candidate pending availability prunes the messages it processed once it is enacted.
+		ParasPendingAvailability::set(vec![a]);
+		assert_noop!(
+			Dmp::force_prune_dmq(RuntimeOrigin::root(), a, 1, 3),
+			Error::<Test>::CandidatePendingAvailability
+		);
+		ParasPendingAvailability::set(vec![]);
+
+		assert_ok!(Dmp::force_prune_dmq(RuntimeOrigin::root(), a, 1, 3));
+		assert_eq!(Dmp::dmq_length(a), 2);
+		assert_eq!(Dmp::dmq_total_bytes(a), 6);
+		assert_last_event(Event::DownwardMessagesProcessed(a, 1, 2).into());
+
+		// pruning the whole queue leaves the MQC head intact.
+		assert_ok!(Dmp::force_prune_dmq(RuntimeOrigin::root(), a, 2, 2));
+		assert_eq!(Dmp::dmq_length(a), 0);
+		assert_eq!(Dmp::dmq_total_bytes(a), 0);
+		assert_eq!(Dmp::dmq_mqc_head(a), head);
+		assert_last_event(Event::DownwardMessagesProcessed(a, 2, 0).into());
+		assert_ok!(Dmp::do_try_state());
+	});
+}
+
+#[test]
+fn sweep_dangling_dmq_works() {
+	let a = ParaId::from(1312);
+
+	new_test_ext(default_genesis_config()).execute_with(|| {
+		// enqueue downward messages to a para that doesn't exist.
+		queue_downward_message(a, vec![1, 2, 3]).unwrap();
+		queue_downward_message(a, vec![4, 5, 6]).unwrap();
+		let head = Dmp::dmq_mqc_head(a);
+
+		// only root can decide that the messages can be dropped.
+		assert_noop!(Dmp::sweep_dangling_dmq(RuntimeOrigin::signed(1), a), BadOrigin);
+		assert_ok!(Dmp::sweep_dangling_dmq(RuntimeOrigin::root(), a));
+		assert_eq!(Dmp::dmq_length(a), 0);
+		assert_eq!(Dmp::dmq_total_bytes(a), 0);
+		assert!(Dmp::dmq_mqc_head(a).is_zero());
+
+		// there is nothing left to sweep.
+		assert_noop!(
+			Dmp::sweep_dangling_dmq(RuntimeOrigin::root(), a),
+			Error::<Test>::NoDanglingQueue
+		);
+
+		// once the para is onboarding its queue is no longer dangling.
+		assert_ok!(Paras::schedule_para_initialize(
+			a,
+			crate::paras::ParaGenesisArgs {
+				para_kind: crate::paras::ParaKind::Parachain,
+				genesis_head: vec![1].into(),
+				validation_code: vec![1].into(),
+			},
+		));
+		queue_downward_message(a, vec![1, 2, 3]).unwrap();
+		queue_downward_message(a, vec![4, 5, 6]).unwrap();
+		assert_noop!(Dmp::sweep_dangling_dmq(RuntimeOrigin::root(), a), Error::<Test>::ParaIsLive);
+
+		// the MQC of the re-onboarded para starts from scratch.
+		assert_eq!(Dmp::dmq_length(a), 2);
+		assert_eq!(Dmp::dmq_mqc_head(a), head);
+		assert_ok!(Dmp::do_try_state());
+	});
+}
+
+#[test]
+fn dmq_contents_recent_returns_newest_first() {
+	let a = ParaId::from(1312);
+
+	new_test_ext(default_genesis_config()).execute_with(|| {
+		assert!(Dmp::dmq_contents_recent(a, 2).is_empty());
+
+		queue_downward_message(a, vec![1]).unwrap();
+		queue_downward_message(a, vec![2]).unwrap();
+		queue_downward_message(a, vec![3]).unwrap();
+
+		let msgs = |recent: Vec<InboundDownwardMessage<BlockNumber>>| {
+			recent.into_iter().map(|m| m.msg).collect::<Vec<_>>()
+		};
+		assert_eq!(msgs(Dmp::dmq_contents_recent(a, 2)), vec![vec![3], vec![2]]);
+		assert_eq!(msgs(Dmp::dmq_contents_recent(a, 5)), vec![vec![3], vec![2], vec![1]]);
+		assert!(Dmp::dmq_contents_recent(a, 0).is_empty());
+
+		// storage is left untouched.
+		assert_eq!(Dmp::dmq_length(a), 3);
+	});
+}
+
+#[test]
+fn dmq_stats_reflects_queue() {
+	let a = ParaId::from(1312);
+
+	new_test_ext(default_genesis_config()).execute_with(|| {
+		assert_eq!(Dmp::dmq_stats(a), DmqStats::default());
+
+		run_to_block(2, None);
+		queue_downward_message(a, vec![1, 2, 3]).unwrap();
+		run_to_block(3, None);
+		queue_downward_message(a, vec![4, 5]).unwrap();
+		assert_eq!(
+			Dmp::dmq_stats(a),
+			DmqStats { message_count: 2, total_bytes: 5, oldest_sent_at: Some(2) }
+		);
+
+		Dmp::prune_dmq(a, 1);
+		assert_eq!(
+			Dmp::dmq_stats(a),
+			DmqStats { message_count: 1, total_bytes: 2, oldest_sent_at: Some(3) }
+		);
+
+		// an empty queue left in storage has no oldest message.
+		Dmp::prune_dmq(a, 1);
+		assert!(DownwardMessageQueues::<Test>::contains_key(&a));
+		assert_eq!(Dmp::dmq_stats(a), DmqStats::default());
+	});
+}
+
+#[test]
+fn dmq_remaining_capacity_reflects_limits() {
+	let a = ParaId::from(1312);
+
+	let mut genesis = default_genesis_config();
+	genesis.configuration.config.max_downward_queue_bytes = 8;
+
+	new_test_ext(genesis).execute_with(|| {
+		let config = Configuration::config();
+		let max_messages = Dmp::dmq_max_length(config.max_downward_message_size) as u64 + 1;
+		assert_eq!(
+			Dmp::dmq_remaining_capacity(&config, a),
+			DmqCapacity { messages_free: max_messages, bytes_free: 8 }
+		);
+
+		queue_downward_message(a, vec![1, 2, 3]).unwrap();
+		queue_downward_message(a, vec![4, 5, 6]).unwrap();
+		assert_eq!(
+			Dmp::dmq_remaining_capacity(&config, a),
+			DmqCapacity { messages_free: max_messages - 2, bytes_free: 2 }
+		);
+
+		// the reported byte budget is exactly what can still be queued.
+		assert!(Dmp::can_queue_downward_message(&config, &a, 3).is_err());
+		queue_downward_message(a, vec![7, 8]).unwrap();
+		assert_eq!(Dmp::dmq_remaining_capacity(&config, a).bytes_free, 0);
+
+		// the byte budget is reported even if the queue size is left at the hard limit.
+		let mut config = config;
+		config.max_downward_queue_bytes = MAX_POSSIBLE_ALLOCATION;
+		assert_eq!(
+			Dmp::dmq_remaining_capacity(&config, a).bytes_free,
+			MAX_POSSIBLE_ALLOCATION as u64 - 8
+		);
+	});
+}
+
+#[test]
+fn expired_downward_messages_are_dropped() {
+	let a = ParaId::from(1312);
+	let b = ParaId::from(228);
+
+	let mut genesis = default_genesis_config();
+	genesis.configuration.config.downward_message_ttl = 5;
+
+	new_test_ext(genesis).execute_with(|| {
+		run_to_block(1, None);
+		queue_downward_message(a, vec![1, 2, 3]).unwrap();
+		run_to_block(3, None);
+		queue_downward_message(a, vec![4, 5]).unwrap();
+		queue_downward_message(b, vec![6]).unwrap();
+		let head = Dmp::dmq_mqc_head(a);
+
+		// messages that are exactly `downward_message_ttl` blocks old are still kept.
+		run_to_block(6, None);
+		assert_eq!(Dmp::dmq_length(a), 2);
+
+		// only the first message of `a` expired.
+		run_to_block(7, None);
+		assert_last_event(Event::DownwardMessagesExpired(a, 1, 1).into());
+		assert_eq!(
+			Dmp::dmq_contents(a),
+			vec![InboundDownwardMessage { sent_at: 3, msg: vec![4, 5] }]
+		);
+		assert_eq!(Dmp::dmq_total_bytes(a), 2);
+		assert_eq!(Dmp::dmq_mqc_head(a), head);
+		assert_eq!(Dmp::dmq_length(b), 1);
+
+		run_to_block(9, None);
+		assert_eq!(Dmp::dmq_length(a), 0);
+		assert_eq!(Dmp::dmq_length(b), 0);
+		assert_eq!(Dmp::dmq_mqc_head(a), head);
+		assert!(Dmp::do_try_state().is_ok());
+	});
+}
+
+#[test]
+fn downward_messages_dont_expire_while_candidate_is_pending_availability() {
+	let a = ParaId::from(1312);
+
+	let mut genesis = default_genesis_config();
+	genesis.configuration.config.downward_message_ttl = 5;
+
+	new_test_ext(genesis).execute_with(|| {
+		run_to_block(1, None);
+		queue_downward_message(a, vec![1, 2, 3]).unwrap();
+
+		// the candidate prunes the messages it processed once it is enacted.
+		ParasPendingAvailability::set(vec![a]);
+		run_to_block(10, None);
+		assert_eq!(Dmp::dmq_length(a), 1);
+
+		ParasPendingAvailability::set(vec![]);
+		run_to_block(11, None);
+		assert_eq!(Dmp::dmq_length(a), 0);
+		assert_last_event(Event::DownwardMessagesExpired(a, 1, 0).into());
+		assert_ok!(Dmp::do_try_state());
+	});
+}
+
+#[test]
+fn downward_messages_dont_expire_if_ttl_is_disabled() {
+	let a = ParaId::from(1312);
+
+	new_test_ext(default_genesis_config()).execute_with(|| {
+		assert!(Configuration::config().downward_message_ttl.is_zero());
+
+		run_to_block(1, None);
+		queue_downward_message(a, vec![1, 2, 3]).unwrap();
+		run_to_block(1000, None);
+		assert_eq!(Dmp::dmq_length(a), 1);
+		assert!(System::events().is_empty());
+	});
+}
+
+#[test]
+fn expiry_checks_are_bounded_per_block() {
+	new_test_ext(default_genesis_config()).execute_with(|| {
+		run_to_block(1, None);
+		for para in 0..=MAX_EXPIRY_CHECKS_PER_BLOCK {
+			queue_downward_message(ParaId::from(para), vec![1, 2, 3]).unwrap();
+		}
+
+		// all the messages expire at once, the sweep starts with the first queue.
+		run_to_block(10, None);
+		ActiveConfig::<Test>::mutate(|config| config.downward_message_ttl = 5);
+
+		// one queue is left for the next block.
+		run_to_block(11, None);
+		assert_eq!(Dmp::paras_with_pending_dmq().count(), 1);
+		assert!(ExpiryCursor::<Test>::get().is_some());
+
+		run_to_block(12, None);
+		assert_eq!(Dmp::paras_with_pending_dmq().count(), 0);
+		assert!(ExpiryCursor::<Test>::get().is_none());
+		assert!(Dmp::do_try_state().is_ok());
+	});
+}
+
+#[test]
+fn on_idle_removes_dangling_queues() {
+	let a = ParaId::from(1312);
+	let b = ParaId::from(228);
+	let c = ParaId::from(123);
+	// never registered, e.g. reserved but not onboarded yet.
+	let d = ParaId::from(456);
+	let genesis_args = crate::paras::ParaGenesisArgs {
+		para_kind: crate::paras::ParaKind::Parachain,
+		genesis_head: vec![1].into(),
+		validation_code: vec![1].into(),
+	};
+
+	new_test_ext(default_genesis_config()).execute_with(|| {
+		assert_ok!(Paras::schedule_para_initialize(b, genesis_args.clone()));
+		Dmp::initializer_on_new_session(&Default::default(), &[a, b, c]);
+		queue_downward_message(a, vec![1, 2, 3]).unwrap();
+		queue_downward_message(b, vec![4, 5, 6]).unwrap();
+		queue_downward_message(c, vec![7, 8, 9]).unwrap();
+		queue_downward_message(d, vec![10]).unwrap();
+
+		// nothing is done without enough weight.
+		assert_eq!(Dmp::on_idle(1, Weight::zero()), Weight::zero());
+		assert_eq!(Dmp::on_idle(1, Weight::from_parts(1_000, 1_000)), Weight::zero());
+		assert_eq!(Dmp::dmq_length(a), 1);
+
+		// one offboarded para is checked per call, those registered again are left alone.
+		for _ in 0..3 {
+			assert!(!Dmp::on_idle(1, Weight::MAX).is_zero());
+		}
+		assert_eq!(Dmp::dmq_length(a), 0);
+		assert!(Dmp::dmq_mqc_head(a).is_zero());
+		assert_eq!(Dmp::dmq_length(c), 0);
+		assert!(Dmp::dmq_mqc_head(c).is_zero());
+		assert_eq!(Dmp::dmq_length(b), 1);
+		assert_eq!(Dmp::dmq_length(d), 1);
+		assert_ok!(Dmp::do_try_state());
+
+		// every offboarded para is checked once.
+		assert_eq!(OffboardedParas::<Test>::iter_keys().count(), 0);
+		queue_downward_message(a, vec![1, 2, 3]).unwrap();
+		assert_eq!(
+			Dmp::on_idle(1, Weight::MAX),
+			<Test as frame_system::Config>::DbWeight::get().reads(1)
+		);
+		assert_eq!(Dmp::dmq_length(a), 1);
+		assert_eq!(Dmp::dmq_length(b), 1);
+		assert_eq!(Dmp::dmq_length(d), 1);
+	});
+}
+
+#[test]
+fn paras_with_pending_dmq_skips_empty_queues() {
+	let a = ParaId::from(1312);
+	let b = ParaId::from(228);
+	let c = ParaId::from(123);
+
+	new_test_ext(default_genesis_config()).execute_with(|| {
+		assert_eq!(Dmp::paras_with_pending_dmq().count(), 0);
+
+		queue_downward_message(a, vec![1, 2, 3]).unwrap();
+		queue_downward_message(b, vec![4, 5, 6]).unwrap();
+		queue_downward_message(c, vec![7, 8, 9]).unwrap();
+
+		// pruning the whole queue leaves an empty queue in storage.
+		Dmp::prune_dmq(b, 1);
+		assert!(DownwardMessageQueues::<Test>::contains_key(&b));
+
+		let mut paras = Dmp::paras_with_pending_dmq().collect::<Vec<_>>();
+		paras.sort();
+		assert_eq!(paras, vec![c, a]);
+	});
+}
+
+#[test]
+fn drain_dmq_weighted_stops_when_out_of_weight() {
+	let a = ParaId::from(1312);
+
+	new_test_ext(default_genesis_config()).execute_with(|| {
+		queue_downward_message(a, vec![1, 2, 3]).unwrap();
+		queue_downward_message(a, vec![4, 5, 6]).unwrap();
+		queue_downward_message(a, vec![7, 8, 9]).unwrap();
+		let mqc_head = Dmp::dmq_mqc_head(a);
+
+		let per_message = |_: &InboundDownwardMessage<BlockNumber>| Weight::from_parts(10, 10);
+
+		// there is only room for two messages.
+		let contents = Dmp::dmq_contents(a);
+		let mut meter = WeightMeter::from_limit(Weight::from_parts(25, 25));
+		assert_eq!(Dmp::drain_dmq_weighted(a, &mut meter, per_message), contents[..2].to_vec());
+		assert_eq!(meter.consumed, Weight::from_parts(20, 20));
+		assert_eq!(Dmp::dmq_contents(a)[0].msg, vec![7, 8, 9]);
+		assert_eq!(Dmp::dmq_length(a), 1);
+
+		// nothing fits into an exhausted meter.
+		assert!(Dmp::drain_dmq_weighted(a, &mut meter, per_message).is_empty());
+		assert_eq!(Dmp::dmq_length(a), 1);
+
+		let mut meter = WeightMeter::max_limit();
+		assert_eq!(Dmp::drain_dmq_weighted(a, &mut meter, per_message), contents[2..].to_vec());
+		assert_eq!(Dmp::dmq_length(a), 0);
+
+		// draining doesn't affect the MQC head.
+		assert_eq!(Dmp::dmq_mqc_head(a), mqc_head);
+	});
+}
+
+#[test]
+fn take_dmq_returns_pruned_messages() {
+	let a = ParaId::from(1312);
+
+	new_test_ext(default_genesis_config()).execute_with(|| {
+		// Events are not deposited in the genesis block.
+		System::set_block_number(1);
+		for i in 0..5u8 {
+			queue_downward_message(a, vec![i; 3]).unwrap();
+		}
+		let contents = Dmp::dmq_contents(a);
+		let mqc_head = Dmp::dmq_mqc_head(a);
+
+		// taking nothing doesn't change anything.
+		assert!(Dmp::take_dmq(a, 0).is_empty());
+		assert_eq!(Dmp::dmq_length(a), 5);
+		assert!(System::events().is_empty());
+
+		let mut taken = Dmp::take_dmq(a, 2);
+		assert_eq!(taken, contents[..2].to_vec());
+		assert_last_event(Event::DownwardMessagesProcessed(a, 2, 3).into());
+		assert_eq!(Dmp::dmq_total_bytes(a), 9);
+
+		// the taken messages followed by the remaining ones are the original contents.
+		taken.extend(Dmp::dmq_contents(a));
+		assert_eq!(taken, contents);
+
+		// taking more than what's pending empties the queue.
+		assert_eq!(Dmp::take_dmq(a, 10), contents[2..].to_vec());
+		assert_eq!(Dmp::dmq_length(a), 0);
+		assert!(Dmp::take_dmq(a, 10).is_empty());
+
+		// taking doesn't affect the MQC head.
+		assert_eq!(Dmp::dmq_mqc_head(a), mqc_head);
+		assert_ok!(Dmp::do_try_state());
+	});
+}
+
+#[test]
+fn dmq_total_bytes_tracked_and_capped() {
+	let a = ParaId::from(1312);
+
+	let mut genesis = default_genesis_config();
+	genesis.configuration.config.max_downward_queue_bytes = 8;
+
+	new_test_ext(genesis).execute_with(|| {
+		assert_eq!(Dmp::dmq_total_bytes(a), 0);
+
+		queue_downward_message(a, vec![1, 2, 3]).unwrap();
+		queue_downward_message(a, vec![4, 5, 6]).unwrap();
+		assert_eq!(Dmp::dmq_total_bytes(a), 6);
+
+		// that would exceed the cap of 8 bytes.
+		assert!(matches!(
+			queue_downward_message(a, vec![7, 8, 9]),
+			Err(QueueDownwardMessageError::QueueBytesExceeded)
+		));
+		assert_eq!(Dmp::dmq_length(a), 2);
+
+		// but this one just fits.
+		queue_downward_message(a, vec![7, 8]).unwrap();
+		assert_eq!(Dmp::dmq_total_bytes(a), 8);
+
+		Dmp::prune_dmq(a, 1);
+		assert_eq!(Dmp::dmq_total_bytes(a), 5);
+
+		Dmp::prune_dmq(a, 2);
+		assert_eq!(Dmp::dmq_total_bytes(a), 0);
+		assert!(!DownwardMessageQueueBytes::<Test>::contains_key(a));
+	});
+}
+
+#[test]
+fn queue_downward_message_respects_pending_limit() {
+	let a = ParaId::from(1312);
+	let b = ParaId::from(228);
+
+	new_test_ext(default_genesis_config()).execute_with(|| {
+		MaxPendingDownwardMessages::set(2);
+
+		queue_downward_message(a, vec![1, 2, 3]).unwrap();
+		queue_downward_message(a, vec![4, 5, 6]).unwrap();
+		let head = Dmp::dmq_mqc_head(a);
+
+		// the limit is reached, nothing is queued.
+		assert!(matches!(
+			queue_downward_message(a, vec![7, 8, 9]),
+			Err(QueueDownwardMessageError::QueueLimitReached)
+		));
+		assert!(matches!(
+			Dmp::can_queue_downward_message(&Configuration::config(), &a, 3),
+			Err(QueueDownwardMessageError::QueueLimitReached)
+		));
+		assert_eq!(Dmp::dmq_length(a), 2);
+		assert_eq!(Dmp::dmq_total_bytes(a), 6);
+		assert_eq!(Dmp::dmq_mqc_head(a), head);
+		assert_eq!(Dmp::dmq_remaining_capacity(&Configuration::config(), a).messages_free, 0);
+		// a full queue is not reported as an oversized message to XCM senders.
+		assert_eq!(
+			SendError::from(QueueDownwardMessageError::QueueLimitReached),
+			SendError::Transport("Downward message queue is full")
+		);
+
+		// the limit is per para.
+		queue_downward_message(b, vec![7, 8, 9]).unwrap();
+
+		// pruning below the limit allows sending again.
+		Dmp::prune_dmq(a, 1);
+		queue_downward_message(a, vec![7, 8, 9]).unwrap();
+		assert_eq!(Dmp::dmq_length(a), 2);
+		assert!(matches!(
+			queue_downward_message(a, vec![10]),
+			Err(QueueDownwardMessageError::QueueLimitReached)
+		));
+	});
+}
+
+#[test]
+fn try_state_checks_queue_consistency() {
+	let a = ParaId::from(1312);
+	let b = ParaId::from(228);
+
+	new_test_ext(default_genesis_config()).execute_with(|| {
+		queue_downward_message(a, vec![1, 2, 3]).unwrap();
+		queue_downward_message(b, vec![4, 5, 6]).unwrap();
+		queue_downward_message(b, vec![7, 8, 9]).unwrap();
+		Dmp::prune_dmq(b, 1);
+		assert_ok!(Dmp::do_try_state());
+
+		// the recorded size of the queue must match its contents.
+		DownwardMessageQueueBytes::<Test>::insert(a, 4);
+		assert!(Dmp::do_try_state().is_err());
+		DownwardMessageQueueBytes::<Test>::insert(a, 3);
+		assert_ok!(Dmp::do_try_state());
+
+		// so must the recorded block of its oldest message.
+		let oldest_sent_at = DownwardMessageQueueOldestSentAt::<Test>::get(a).unwrap();
+		DownwardMessageQueueOldestSentAt::<Test>::insert(a, oldest_sent_at + 1);
+		assert!(Dmp::do_try_state().is_err());
+		DownwardMessageQueueOldestSentAt::<Test>::insert(a, oldest_sent_at);
+		assert_ok!(Dmp::do_try_state());
+
+		// a queue can't outlive its size.
+		DownwardMessageQueues::<Test>::remove(a);
+		assert!(Dmp::do_try_state().is_err());
 	});
 }
 
@@ -185,6 +1073,69 @@ fn queue_downward_message_critical() {
 	});
 }
 
+#[test]
+fn can_queue_downward_message_checks_without_queueing() {
+	let a = ParaId::from(1312);
+
+	let mut genesis = default_genesis_config();
+	genesis.configuration.config.max_downward_message_size = 7;
+
+	new_test_ext(genesis).execute_with(|| {
+		let config = Configuration::config();
+
+		assert!(Dmp::can_queue_downward_message(&config, &a, 7).is_ok());
+		assert!(Dmp::can_queue_downward_message(&config, &a, 8).is_err());
+
+		// the check itself doesn't enqueue anything.
+		assert_eq!(Dmp::dmq_length(a), 0);
+		assert!(Dmp::dmq_mqc_head(a).is_zero());
+	});
+}
+
+#[test]
+fn queue_downward_message_to_offboarding_para() {
+	let a = ParaId::from(1312);
+
+	let mut genesis = default_genesis_config();
+	genesis.paras.paras = vec![(
+		a,
+		crate::paras::ParaGenesisArgs {
+			para_kind: crate::paras::ParaKind::Parachain,
+			genesis_head: vec![1].into(),
+			validation_code: vec![1].into(),
+		},
+	)];
+
+	new_test_ext(genesis).execute_with(|| {
+		let mut config = Configuration::config();
+		config.dmp_reject_offboarding_recipients = true;
+
+		assert_ok!(queue_downward_message(a, vec![1, 2, 3]));
+		assert_ok!(Paras::schedule_para_cleanup(a));
+
+		// the para is only removed at the next session change, so without the check the message
+		// is accepted and dropped along with the queue later.
+		assert!(!Configuration::config().dmp_reject_offboarding_recipients);
+		assert_ok!(queue_downward_message(a, vec![4, 5, 6]));
+
+		assert!(matches!(
+			Dmp::can_queue_downward_message(&config, &a, 3),
+			Err(QueueDownwardMessageError::RecipientOffboarding)
+		));
+		assert!(matches!(
+			Dmp::queue_downward_message(&config, a, vec![7, 8, 9]),
+			Err(QueueDownwardMessageError::RecipientOffboarding)
+		));
+		assert_eq!(Dmp::dmq_length(a), 2);
+		assert_eq!(Dmp::dmq_remaining_capacity(&config, a), DmqCapacity::default());
+		assert!(Dmp::dmq_remaining_capacity(&Configuration::config(), a).messages_free > 0);
+		assert!(matches!(
+			SendError::from(QueueDownwardMessageError::RecipientOffboarding),
+			SendError::Unroutable
+		));
+	});
+}
+
 #[test]
 fn verify_dmq_mqc_head_is_externally_accessible() {
 	use hex_literal::hex;
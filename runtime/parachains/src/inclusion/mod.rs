@@ -25,7 +25,7 @@ use crate::{
 	scheduler::CoreAssignment, shared, ump,
 };
 use bitvec::{order::Lsb0 as BitOrderLsb0, vec::BitVec};
-use frame_support::pallet_prelude::*;
+use frame_support::{pallet_prelude::*, traits::Contains};
 use parity_scale_codec::{Decode, Encode};
 use primitives::{
 	supermajority_threshold, AvailabilityBitfield, BackedCandidate, CandidateCommitments,
@@ -898,6 +898,15 @@ impl<T: Config> Pallet<T> {
 	}
 }
 
+/// The paras that have a candidate pending availability.
+pub struct ParasPendingAvailability<T>(sp_std::marker::PhantomData<T>);
+
+impl<T: Config> Contains<ParaId> for ParasPendingAvailability<T> {
+	fn contains(para: &ParaId) -> bool {
+		<PendingAvailability<T>>::contains_key(para)
+	}
+}
+
 const fn availability_threshold(n_validators: usize) -> usize {
 	supermajority_threshold(n_validators)
 }
@@ -1058,7 +1067,11 @@ impl<T: Config> CandidateCheckContext<T> {
 		}
 
 		// check if the candidate passes the messaging acceptance criteria
-		<dmp::Pallet<T>>::check_processed_downward_messages(para_id, processed_downward_messages)?;
+		<dmp::Pallet<T>>::check_processed_downward_messages(
+			&self.config,
+			para_id,
+			processed_downward_messages,
+		)?;
 		<ump::Pallet<T>>::check_upward_messages(&self.config, para_id, upward_messages)?;
 		<hrmp::Pallet<T>>::check_hrmp_watermark(para_id, self.relay_parent_number, hrmp_watermark)?;
 		<hrmp::Pallet<T>>::check_outbound_hrmp(&self.config, para_id, horizontal_messages)?;
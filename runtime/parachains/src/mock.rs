@@ -25,7 +25,7 @@ use crate::{
 
 use frame_support::{
 	parameter_types,
-	traits::{ConstU32, GenesisBuild, ValidatorSet, ValidatorSetWithIdentification},
+	traits::{ConstU32, GenesisBuild, IsInVec, ValidatorSet, ValidatorSetWithIdentification},
 	weights::Weight,
 };
 use frame_support_test::TestRandomness;
@@ -215,7 +215,17 @@ impl crate::paras::Config for Test {
 	type NextSessionRotation = TestNextSessionRotation;
 }
 
-impl crate::dmp::Config for Test {}
+parameter_types! {
+	pub static MaxPendingDownwardMessages: u32 = u32::MAX;
+	pub static ParasPendingAvailability: Vec<ParaId> = Vec::new();
+}
+
+impl crate::dmp::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type WeightInfo = crate::dmp::TestWeightInfo;
+	type ParasPendingAvailability = IsInVec<ParasPendingAvailability>;
+	type MaxPendingDownwardMessages = MaxPendingDownwardMessages;
+}
 
 parameter_types! {
 	pub const FirstMessageFactorPercent: u64 = 100;
@@ -15,3 +15,43 @@
 // along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
 
 //! Put implementations of functions from staging APIs here.
+
+use crate::{configuration, dmp};
+use primitives::{
+	vstaging::{DmqCapacity, DmqStats, ProcessedDownwardMessagesAcceptanceErr},
+	Hash, Id as ParaId,
+};
+
+/// Implementation for the `dmq_mqc_head` function of the runtime API.
+pub fn dmq_mqc_head<T: dmp::Config>(recipient: ParaId) -> Hash {
+	<dmp::Pallet<T>>::dmq_mqc_head(recipient)
+}
+
+/// Implementation for the `dmq_stats` function of the runtime API.
+pub fn dmq_stats<T: dmp::Config>(recipient: ParaId) -> DmqStats<T::BlockNumber> {
+	<dmp::Pallet<T>>::dmq_stats(recipient)
+}
+
+/// Implementation for the `dmq_remaining_capacity` function of the runtime API.
+pub fn dmq_remaining_capacity<T: dmp::Config>(recipient: ParaId) -> DmqCapacity {
+	let config = <configuration::Pallet<T>>::config();
+	<dmp::Pallet<T>>::dmq_remaining_capacity(&config, recipient)
+}
+
+/// Implementation for the `dmq_last_pruned_mqc_head` function of the runtime API.
+pub fn dmq_last_pruned_mqc_head<T: dmp::Config>(recipient: ParaId) -> Option<Hash> {
+	<dmp::Pallet<T>>::dmq_last_pruned_mqc_head(recipient)
+}
+
+/// Implementation for the `check_processed_downward_messages` function of the runtime API.
+pub fn check_processed_downward_messages<T: dmp::Config>(
+	recipient: ParaId,
+	processed_downward_messages: u32,
+) -> Result<(), ProcessedDownwardMessagesAcceptanceErr> {
+	let config = <configuration::Pallet<T>>::config();
+	<dmp::Pallet<T>>::check_processed_downward_messages(
+		&config,
+		recipient,
+		processed_downward_messages,
+	)
+}
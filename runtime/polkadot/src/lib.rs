@@ -1124,7 +1124,18 @@ impl parachains_ump::Config for Runtime {
 	type WeightInfo = weights::runtime_parachains_ump::WeightInfo<Self>;
 }
 
-impl parachains_dmp::Config for Runtime {}
+parameter_types! {
+	// Stays below the hard limit of `MAX_POSSIBLE_ALLOCATION / max_downward_message_size` messages
+	// of the host configuration, which is 655 for a `max_downward_message_size` of 51200 bytes.
+	pub const MaxPendingDownwardMessages: u32 = 512;
+}
+
+impl parachains_dmp::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type WeightInfo = ();
+	type ParasPendingAvailability = parachains_inclusion::ParasPendingAvailability<Runtime>;
+	type MaxPendingDownwardMessages = MaxPendingDownwardMessages;
+}
 
 impl parachains_hrmp::Config for Runtime {
 	type RuntimeOrigin = RuntimeOrigin;
@@ -1385,7 +1396,7 @@ construct_runtime! {
 		ParaScheduler: parachains_scheduler::{Pallet, Storage} = 55,
 		Paras: parachains_paras::{Pallet, Call, Storage, Event, Config, ValidateUnsigned} = 56,
 		Initializer: parachains_initializer::{Pallet, Call, Storage} = 57,
-		Dmp: parachains_dmp::{Pallet, Storage} = 58,
+		Dmp: parachains_dmp::{Pallet, Call, Storage, Event} = 58,
 		Ump: parachains_ump::{Pallet, Call, Storage, Event} = 59,
 		Hrmp: parachains_hrmp::{Pallet, Call, Storage, Event<T>, Config} = 60,
 		ParaSessionInfo: parachains_session_info::{Pallet, Storage} = 61,
@@ -1460,7 +1471,10 @@ pub mod migrations {
 	);
 
 	/// Unreleased migrations. Add new ones here:
-	pub type Unreleased = ();
+	pub type Unreleased = (
+		parachains_configuration::migration::v6::MigrateToV6<Runtime>,
+		parachains_dmp::migration::v1::MigrateToV1<Runtime>,
+	);
 }
 
 /// Unchecked extrinsic type as expected by this runtime.
@@ -1497,6 +1511,7 @@ mod benches {
 		[runtime_parachains::configuration, Configuration]
 		[runtime_parachains::disputes, ParasDisputes]
 		[runtime_parachains::disputes::slashing, ParasSlashing]
+		[runtime_parachains::dmp, Dmp]
 		[runtime_parachains::hrmp, Hrmp]
 		[runtime_parachains::initializer, Initializer]
 		[runtime_parachains::paras, Paras]
@@ -2066,6 +2081,25 @@ mod test_fees {
 		assert!(payout_weight * 2f64 < block_weight);
 	}
 
+	#[test]
+	fn dmp_initializer_weight_portion() {
+		use parachains_dmp::WeightInfo;
+		let base_weight =
+			<Runtime as parachains_dmp::Config>::WeightInfo::initializer_initialize(0);
+		let weight = <Runtime as parachains_dmp::Config>::WeightInfo::initializer_initialize(100)
+			.ref_time() as f64;
+		let block_weight = BlockWeights::get().max_block.ref_time() as f64;
+
+		println!(
+			"checking 100 downward message queues takes {:.2} of the block weight [{} / {}]",
+			weight / block_weight,
+			weight,
+			block_weight
+		);
+		assert!(base_weight.ref_time() > 0);
+		assert!(weight * 10f64 < block_weight);
+	}
+
 	#[test]
 	fn block_cost() {
 		let max_block_weight = BlockWeights::get().max_block;
@@ -39,12 +39,15 @@ use sp_std::{cmp::Ordering, collections::btree_map::BTreeMap, prelude::*};
 
 use runtime_parachains::{
 	configuration as parachains_configuration, disputes as parachains_disputes,
-	disputes::slashing as parachains_slashing, dmp as parachains_dmp, hrmp as parachains_hrmp,
-	inclusion as parachains_inclusion, initializer as parachains_initializer,
-	origin as parachains_origin, paras as parachains_paras,
+	disputes::slashing as parachains_slashing,
+	dmp as parachains_dmp, hrmp as parachains_hrmp, inclusion as parachains_inclusion,
+	initializer as parachains_initializer, origin as parachains_origin, paras as parachains_paras,
 	paras_inherent as parachains_paras_inherent,
-	runtime_api_impl::v4 as parachains_runtime_api_impl, scheduler as parachains_scheduler,
-	session_info as parachains_session_info, shared as parachains_shared, ump as parachains_ump,
+	runtime_api_impl::{
+		v4 as parachains_runtime_api_impl, vstaging as parachains_staging_runtime_api_impl,
+	},
+	scheduler as parachains_scheduler, session_info as parachains_session_info,
+	shared as parachains_shared, ump as parachains_ump,
 };
 
 use authority_discovery_primitives::AuthorityId as AuthorityDiscoveryId;
@@ -1051,7 +1054,18 @@ impl parachains_ump::Config for Runtime {
 	type WeightInfo = weights::runtime_parachains_ump::WeightInfo<Runtime>;
 }
 
-impl parachains_dmp::Config for Runtime {}
+parameter_types! {
+	// Stays below the hard limit of `MAX_POSSIBLE_ALLOCATION / max_downward_message_size` messages
+	// of the host configuration, which is 655 for a `max_downward_message_size` of 51200 bytes.
+	pub const MaxPendingDownwardMessages: u32 = 512;
+}
+
+impl parachains_dmp::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type WeightInfo = ();
+	type ParasPendingAvailability = parachains_inclusion::ParasPendingAvailability<Runtime>;
+	type MaxPendingDownwardMessages = MaxPendingDownwardMessages;
+}
 
 impl parachains_hrmp::Config for Runtime {
 	type RuntimeOrigin = RuntimeOrigin;
@@ -1413,7 +1427,7 @@ construct_runtime! {
 		ParaScheduler: parachains_scheduler::{Pallet, Storage} = 55,
 		Paras: parachains_paras::{Pallet, Call, Storage, Event, Config, ValidateUnsigned} = 56,
 		Initializer: parachains_initializer::{Pallet, Call, Storage} = 57,
-		Dmp: parachains_dmp::{Pallet, Storage} = 58,
+		Dmp: parachains_dmp::{Pallet, Call, Storage, Event} = 58,
 		Ump: parachains_ump::{Pallet, Call, Storage, Event} = 59,
 		Hrmp: parachains_hrmp::{Pallet, Call, Storage, Event<T>, Config} = 60,
 		ParaSessionInfo: parachains_session_info::{Pallet, Storage} = 61,
@@ -1495,7 +1509,10 @@ pub mod migrations {
 	);
 
 	/// Unreleased migrations. Add new ones here:
-	pub type Unreleased = ();
+	pub type Unreleased = (
+		parachains_configuration::migration::v6::MigrateToV6<Runtime>,
+		parachains_dmp::migration::v1::MigrateToV1<Runtime>,
+	);
 }
 
 /// Executive: handles dispatch to the various modules.
@@ -1553,6 +1570,7 @@ mod benches {
 		[runtime_parachains::configuration, Configuration]
 		[runtime_parachains::hrmp, Hrmp]
 		[runtime_parachains::disputes, ParasDisputes]
+		[runtime_parachains::dmp, Dmp]
 		[runtime_parachains::initializer, Initializer]
 		[runtime_parachains::paras_inherent, ParaInherent]
 		[runtime_parachains::paras, Paras]
@@ -1660,6 +1678,7 @@ sp_api::impl_runtime_apis! {
 		}
 	}
 
+	#[api_version(5)]
 	impl primitives::runtime_api::ParachainHost<Block, Hash, BlockNumber> for Runtime {
 		fn validators() -> Vec<ValidatorId> {
 			parachains_runtime_api_impl::validators::<Runtime>()
@@ -1765,6 +1784,32 @@ sp_api::impl_runtime_apis! {
 		fn disputes() -> Vec<(SessionIndex, CandidateHash, DisputeState<BlockNumber>)> {
 			parachains_runtime_api_impl::get_session_disputes::<Runtime>()
 		}
+
+		fn dmq_mqc_head(recipient: ParaId) -> Hash {
+			parachains_staging_runtime_api_impl::dmq_mqc_head::<Runtime>(recipient)
+		}
+
+		fn dmq_stats(recipient: ParaId) -> primitives::vstaging::DmqStats<BlockNumber> {
+			parachains_staging_runtime_api_impl::dmq_stats::<Runtime>(recipient)
+		}
+
+		fn dmq_remaining_capacity(recipient: ParaId) -> primitives::vstaging::DmqCapacity {
+			parachains_staging_runtime_api_impl::dmq_remaining_capacity::<Runtime>(recipient)
+		}
+
+		fn dmq_last_pruned_mqc_head(recipient: ParaId) -> Option<Hash> {
+			parachains_staging_runtime_api_impl::dmq_last_pruned_mqc_head::<Runtime>(recipient)
+		}
+
+		fn check_processed_downward_messages(
+			recipient: ParaId,
+			processed_downward_messages: u32,
+		) -> Result<(), primitives::vstaging::ProcessedDownwardMessagesAcceptanceErr> {
+			parachains_staging_runtime_api_impl::check_processed_downward_messages::<Runtime>(
+				recipient,
+				processed_downward_messages,
+			)
+		}
 	}
 
 	#[api_version(2)]
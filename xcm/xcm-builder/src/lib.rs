@@ -70,7 +70,8 @@ pub use nonfungibles_adapter::{
 
 mod weight;
 pub use weight::{
-	FixedRateOfFungible, FixedWeightBounds, TakeRevenue, UsingComponents, WeightInfoBounds,
+	DestinationWeightBounds, FixedRateOfFungible, FixedWeightBounds, RoundDown, RoundNearest,
+	RoundingMode, RouterWeigher, TakeRevenue, UsingComponents, WeightInfoBounds,
 };
 
 mod matches_token;
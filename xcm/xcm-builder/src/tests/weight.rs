@@ -16,6 +16,20 @@
 
 use super::*;
 
+parameter_types! {
+	pub static HereWeightPrice: (AssetId, u128, u128) =
+		(Here.into(), WEIGHT_REF_TIME_PER_SECOND.into(), WEIGHT_PROOF_SIZE_PER_MB.into());
+	pub static Para1WeightPrice: (AssetId, u128, u128) =
+		(Parachain(1).into(), WEIGHT_REF_TIME_PER_SECOND.into(), WEIGHT_PROOF_SIZE_PER_MB.into());
+}
+
+type Traders = (
+	// trader one
+	FixedRateOfFungible<HereWeightPrice, ()>,
+	// trader two
+	FixedRateOfFungible<Para1WeightPrice, ()>,
+);
+
 #[test]
 fn fixed_rate_of_fungible_should_work() {
 	parameter_types! {
@@ -47,6 +61,94 @@ fn fixed_rate_of_fungible_should_work() {
 	);
 }
 
+#[test]
+fn fixed_rate_of_fungible_should_round_refunds() {
+	parameter_types! {
+		// half a unit of asset per unit of ref time.
+		pub static HalfWeightPrice: (AssetId, u128, u128) =
+			(Here.into(), (WEIGHT_REF_TIME_PER_SECOND / 2).into(), 0);
+	}
+
+	let mut trader = FixedRateOfFungible::<HalfWeightPrice, ()>::new();
+	assert_eq!(
+		trader.buy_weight(Weight::from_parts(10, 0), fungible_multi_asset(Here.into(), 10).into()),
+		Ok(fungible_multi_asset(Here.into(), 5).into()),
+	);
+	// 1.5 rounds down to 1.
+	assert_eq!(trader.refund_weight(Weight::from_parts(3, 0)), Some((Here, 1u128).into()));
+	// 2.5 rounds down to 2.
+	assert_eq!(trader.refund_weight(Weight::from_parts(5, 0)), Some((Here, 2u128).into()));
+	assert_eq!(trader.refund_weight(Weight::from_parts(2, 0)), Some((Here, 1u128).into()));
+
+	let mut trader = FixedRateOfFungible::<HalfWeightPrice, (), RoundNearest>::new();
+	assert_eq!(
+		trader.buy_weight(Weight::from_parts(10, 0), fungible_multi_asset(Here.into(), 10).into()),
+		Ok(fungible_multi_asset(Here.into(), 5).into()),
+	);
+	// 1.5 rounds up to 2.
+	assert_eq!(trader.refund_weight(Weight::from_parts(3, 0)), Some((Here, 2u128).into()));
+	// 2.5 rounds up to 3.
+	assert_eq!(trader.refund_weight(Weight::from_parts(5, 0)), Some((Here, 3u128).into()));
+	// the whole payment was refunded already.
+	assert_eq!(trader.refund_weight(Weight::from_parts(2, 0)), None);
+}
+
+#[test]
+fn fixed_rate_of_fungible_should_price_proof_size_separately() {
+	parameter_types! {
+		// one unit of asset per unit of ref time, three per unit of proof size.
+		pub static TwoDimensionalPrice: (AssetId, u128, u128) =
+			(Here.into(), WEIGHT_REF_TIME_PER_SECOND.into(), (3 * WEIGHT_PROOF_SIZE_PER_MB).into());
+	}
+
+	// same ref time, different proof size.
+	let mut trader = FixedRateOfFungible::<TwoDimensionalPrice, ()>::new();
+	assert_eq!(
+		trader.buy_weight(Weight::from_parts(10, 0), fungible_multi_asset(Here.into(), 100).into()),
+		Ok(fungible_multi_asset(Here.into(), 90).into()),
+	);
+	let mut trader = FixedRateOfFungible::<TwoDimensionalPrice, ()>::new();
+	assert_eq!(
+		trader
+			.buy_weight(Weight::from_parts(10, 10), fungible_multi_asset(Here.into(), 100).into()),
+		Ok(fungible_multi_asset(Here.into(), 60).into()),
+	);
+
+	// refunds are priced per dimension as well.
+	assert_eq!(trader.refund_weight(Weight::from_parts(0, 5)), Some((Here, 15u128).into()));
+	assert_eq!(trader.refund_weight(Weight::from_parts(5, 0)), Some((Here, 5u128).into()));
+	assert_eq!(trader.refund_weight(Weight::from_parts(5, 5)), Some((Here, 20u128).into()));
+	assert_eq!(trader.refund_weight(Weight::from_parts(1, 1)), None);
+}
+
+#[test]
+fn fixed_rate_of_fungible_partial_purchase_should_not_exceed_payment() {
+	parameter_types! {
+		// 1.2 units of asset per unit of ref time and per unit of proof size.
+		pub static FractionalWeightPrice: (AssetId, u128, u128) = (
+			Here.into(),
+			(6 * WEIGHT_REF_TIME_PER_SECOND / 5).into(),
+			(6 * WEIGHT_PROOF_SIZE_PER_MB / 5).into(),
+		);
+	}
+
+	let mut trader = FixedRateOfFungible::<FractionalWeightPrice, ()>::new();
+	// `(14, 14)` costs 16 + 16 = 32, more than the 23 supplied.
+	assert_err!(
+		trader.buy_weight(Weight::from_parts(14, 14), fungible_multi_asset(Here.into(), 23).into()),
+		XcmError::TooExpensive,
+	);
+	// scaling by 23 / 32 gives `(10, 10)`, which costs 12 + 12 = 24, so `(9, 9)` is bought for
+	// 10 + 10 = 20 instead.
+	assert_eq!(
+		trader.buy_partial_weight(
+			Weight::from_parts(14, 14),
+			fungible_multi_asset(Here.into(), 23).into()
+		),
+		Ok((Weight::from_parts(9, 9), fungible_multi_asset(Here.into(), 3).into())),
+	);
+}
+
 #[test]
 fn errors_should_return_unused_weight() {
 	// we'll let them have message execution for free.
@@ -134,20 +236,6 @@ fn weight_trader_tuple_should_work() {
 	let para_1: MultiLocation = Parachain(1).into();
 	let para_2: MultiLocation = Parachain(2).into();
 
-	parameter_types! {
-		pub static HereWeightPrice: (AssetId, u128, u128) =
-			(Here.into(), WEIGHT_REF_TIME_PER_SECOND.into(), WEIGHT_PROOF_SIZE_PER_MB.into());
-		pub static Para1WeightPrice: (AssetId, u128, u128) =
-			(Parachain(1).into(), WEIGHT_REF_TIME_PER_SECOND.into(), WEIGHT_PROOF_SIZE_PER_MB.into());
-	}
-
-	type Traders = (
-		// trader one
-		FixedRateOfFungible<HereWeightPrice, ()>,
-		// trader two
-		FixedRateOfFungible<Para1WeightPrice, ()>,
-	);
-
 	let mut traders = Traders::new();
 	// trader one buys weight
 	assert_eq!(
@@ -181,3 +269,150 @@ fn weight_trader_tuple_should_work() {
 	// and no refund
 	assert_eq!(traders.refund_weight(Weight::from_parts(2, 2)), None);
 }
+
+#[test]
+fn weight_trader_tuple_should_report_charged_asset() {
+	let para_1: MultiLocation = Parachain(1).into();
+
+	// the second trader is charged, and the surplus is returned.
+	let mut traders = Traders::new();
+	assert_eq!(
+		traders
+			.buy_weight_detailed(Weight::from_parts(5, 5), fungible_multi_asset(para_1, 15).into()),
+		Ok((fungible_multi_asset(para_1, 5).into(), Some(fungible_multi_asset(para_1, 10)))),
+	);
+
+	// nothing is charged for free weight.
+	let mut traders = Traders::new();
+	assert_eq!(
+		traders.buy_weight_detailed(Weight::zero(), fungible_multi_asset(para_1, 15).into()),
+		Ok((fungible_multi_asset(para_1, 15).into(), None)),
+	);
+}
+
+#[test]
+fn weight_trader_tuple_should_split_payment() {
+	let para_1: MultiLocation = Parachain(1).into();
+
+	let payment: Assets =
+		vec![fungible_multi_asset(Here.into(), 4), fungible_multi_asset(para_1, 7)].into();
+
+	let mut traders = Traders::new();
+	// no single asset can pay for the weight
+	assert_err!(
+		traders.buy_weight(Weight::from_parts(5, 5), payment.clone()),
+		XcmError::TooExpensive,
+	);
+
+	let mut traders = Traders::new();
+	// trader one buys `(2, 2)` with all of its asset; trader two buys the remaining `(3, 3)`
+	assert_eq!(
+		traders.buy_partial_weight(Weight::from_parts(5, 5), payment.clone()),
+		Ok((Weight::from_parts(5, 5), fungible_multi_asset(para_1, 1).into())),
+	);
+	// trader one refunds first
+	assert_eq!(
+		traders.refund_weight(Weight::from_parts(1, 1)),
+		Some(fungible_multi_asset(Here.into(), 2))
+	);
+
+	let mut traders = Traders::new();
+	// not enough to purchase the weight with both assets combined, only `(5, 5)` is bought
+	assert_eq!(
+		traders.buy_partial_weight(Weight::from_parts(10, 10), payment),
+		Ok((Weight::from_parts(5, 5), fungible_multi_asset(para_1, 1).into())),
+	);
+}
+
+#[test]
+fn weight_trader_tuple_should_refund_all_assets() {
+	let para_1: MultiLocation = Parachain(1).into();
+
+	let mut traders = Traders::new();
+	// trader one buys `(2, 2)`, trader two buys `(3, 3)`
+	assert_eq!(
+		traders.buy_weight(Weight::from_parts(2, 2), fungible_multi_asset(Here.into(), 4).into()),
+		Ok(vec![].into()),
+	);
+	assert_eq!(
+		traders.buy_weight(Weight::from_parts(3, 3), fungible_multi_asset(para_1, 6).into()),
+		Ok(vec![].into()),
+	);
+
+	// both traders refund
+	assert_eq!(
+		traders.refund_weight_multi(Weight::from_parts(1, 1)),
+		vec![fungible_multi_asset(Here.into(), 2), fungible_multi_asset(para_1, 2)].into(),
+	);
+	// trader one can't refund more than it has bought
+	assert_eq!(
+		traders.refund_weight_multi(Weight::from_parts(2, 2)),
+		vec![fungible_multi_asset(Here.into(), 2), fungible_multi_asset(para_1, 4)].into(),
+	);
+	// nothing left to refund
+	assert_eq!(traders.refund_weight_multi(Weight::from_parts(2, 2)), Assets::new());
+}
+
+#[test]
+fn router_weigher_should_use_bounds_of_destination() {
+	use xcm_executor::traits::UniversalWeigher;
+
+	#[derive(Clone)]
+	enum DestKind {
+		Relay,
+		Para,
+	}
+
+	struct KindOfDest;
+	impl Convert<MultiLocation, DestKind> for KindOfDest {
+		fn convert(dest: MultiLocation) -> Result<DestKind, MultiLocation> {
+			match dest {
+				MultiLocation { parents: 1, interior: Here } => Ok(DestKind::Relay),
+				MultiLocation { parents: 1, interior: X1(Parachain(_)) } => Ok(DestKind::Para),
+				_ => Err(dest),
+			}
+		}
+	}
+
+	parameter_types! {
+		pub const RelayUnitWeight: Weight = Weight::from_parts(10, 10);
+		pub const ParaUnitWeight: Weight = Weight::from_parts(20, 20);
+	}
+
+	struct DestBounds;
+	impl DestinationWeightBounds for DestBounds {
+		type Kind = DestKind;
+		fn weight(kind: DestKind, message: Xcm<()>) -> Result<Weight, ()> {
+			match kind {
+				DestKind::Relay => Self::weigh_with::<
+					TestCall,
+					FixedWeightBounds<RelayUnitWeight, TestCall, ConstU32<10>>,
+				>(message),
+				DestKind::Para => Self::weigh_with::<
+					TestCall,
+					FixedWeightBounds<ParaUnitWeight, TestCall, ConstU32<10>>,
+				>(message),
+			}
+		}
+	}
+
+	type Weigher = RouterWeigher<KindOfDest, DestBounds>;
+
+	let message = Xcm::<()>(vec![
+		ClearOrigin,
+		Transact {
+			origin_kind: OriginKind::Native,
+			require_weight_at_most: Weight::from_parts(5, 5),
+			call: vec![].into(),
+		},
+	]);
+	assert_eq!(Weigher::weigh(Parent, message.clone()), Ok(Weight::from_parts(25, 25)));
+	assert_eq!(
+		Weigher::weigh((Parent, Parachain(1)), message.clone()),
+		Ok(Weight::from_parts(45, 45))
+	);
+	// unknown destination.
+	assert_eq!(Weigher::weigh(Parachain(1), message.clone()), Err(()));
+	// the limits of the destination's bounds still apply.
+	assert_eq!(Weigher::weigh(Parent, Xcm(vec![ClearOrigin; 11])), Err(()));
+}
@@ -23,11 +23,14 @@ use frame_support::{
 	},
 };
 use parity_scale_codec::Decode;
-use sp_runtime::traits::{SaturatedConversion, Saturating, Zero};
+use sp_runtime::{
+	traits::{SaturatedConversion, Saturating, Zero},
+	Perquintill,
+};
 use sp_std::{marker::PhantomData, result::Result};
 use xcm::latest::{prelude::*, Weight};
 use xcm_executor::{
-	traits::{WeightBounds, WeightTrader},
+	traits::{Convert, UniversalWeigher, WeightBounds, WeightTrader},
 	Assets,
 };
 
@@ -114,6 +117,44 @@ where
 	}
 }
 
+/// Weighs messages with the `WeightBounds` that applies to a kind of destination.
+pub trait DestinationWeightBounds {
+	/// The kinds of destinations whose messages are weighed differently.
+	type Kind: Clone;
+
+	/// Return the maximum amount of weight that an attempted execution of `message` at a
+	/// destination of the given `kind` could consume.
+	fn weight(kind: Self::Kind, message: Xcm<()>) -> Result<Weight, ()>;
+
+	/// Weigh `message` with `Bounds`, adapting it to the `RuntimeCall` type of `Bounds`.
+	///
+	/// The calls of any `Transact` instruction are kept encoded, so they are never decoded as
+	/// `RuntimeCall`.
+	fn weigh_with<RuntimeCall, Bounds: WeightBounds<RuntimeCall>>(
+		message: Xcm<()>,
+	) -> Result<Weight, ()> {
+		Bounds::weight(&mut Xcm::<RuntimeCall>::from(message))
+	}
+}
+
+/// A `UniversalWeigher` which weighs a message with the `WeightBounds` of its destination.
+///
+/// `Resolver` tells which kind of executor runs at a destination, and `Bounds` weighs the message
+/// for that kind. Weighing fails for destinations which `Resolver` doesn't know about.
+pub struct RouterWeigher<Resolver, Bounds>(PhantomData<(Resolver, Bounds)>);
+impl<Resolver, Bounds> UniversalWeigher for RouterWeigher<Resolver, Bounds>
+where
+	Resolver: Convert<MultiLocation, Bounds::Kind>,
+	Bounds: DestinationWeightBounds,
+{
+	fn weigh(dest: impl Into<MultiLocation>, message: Xcm<()>) -> Result<Weight, ()> {
+		let kind = Resolver::convert(dest.into()).map_err(|dest| {
+			log::trace!(target: "xcm::weight", "RouterWeigher unknown destination: {:?}", dest);
+		})?;
+		Bounds::weight(kind, message)
+	}
+}
+
 /// Function trait for handling some revenue. Similar to a negative imbalance (credit) handler, but for a
 /// `MultiAsset`. Sensible implementations will deposit the asset in some known treasury or block-author account.
 pub trait TakeRevenue {
@@ -126,47 +167,149 @@ impl TakeRevenue for () {
 	fn take_revenue(_revenue: MultiAsset) {}
 }
 
+/// How the amount of an asset is rounded when converting it from weight.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum RoundingMode {
+	/// Round down, in favour of the chain.
+	Down,
+	/// Round to the nearest amount, with halves rounded up.
+	Nearest,
+}
+
+impl RoundingMode {
+	/// Computes `x * numerator / denominator` rounded according to `self`.
+	fn mul_div(self, x: u128, numerator: u128, denominator: u128) -> u128 {
+		let product = x.saturating_mul(numerator);
+		match self {
+			RoundingMode::Down => product / denominator,
+			RoundingMode::Nearest => product.saturating_add(denominator / 2) / denominator,
+		}
+	}
+}
+
+/// Rounds weight refunds down. This is the default for `FixedRateOfFungible`.
+pub struct RoundDown;
+impl Get<RoundingMode> for RoundDown {
+	fn get() -> RoundingMode {
+		RoundingMode::Down
+	}
+}
+
+/// Rounds weight refunds to the nearest amount.
+pub struct RoundNearest;
+impl Get<RoundingMode> for RoundNearest {
+	fn get() -> RoundingMode {
+		RoundingMode::Nearest
+	}
+}
+
 /// Simple fee calculator that requires payment in a single fungible at a fixed rate.
 ///
 /// The constant `Get` type parameter should be the fungible ID, the amount of it required for one
-/// second of weight and the amount required for 1 MB of proof.
-pub struct FixedRateOfFungible<T: Get<(AssetId, u128, u128)>, R: TakeRevenue>(
-	Weight,
-	u128,
-	PhantomData<(T, R)>,
-);
-impl<T: Get<(AssetId, u128, u128)>, R: TakeRevenue> WeightTrader for FixedRateOfFungible<T, R> {
+/// second of weight and the amount required for 1 MB of proof. Both dimensions of the weight are
+/// priced independently and the fee is their sum, so a message heavy on proof size costs more than
+/// one with the same ref time and a small proof.
+///
+/// Weight is always bought at the rounded down price. `M` decides how refunds are rounded; a refund
+/// never exceeds the amount that was paid.
+pub struct FixedRateOfFungible<
+	T: Get<(AssetId, u128, u128)>,
+	R: TakeRevenue,
+	M: Get<RoundingMode> = RoundDown,
+>(Weight, u128, PhantomData<(T, R, M)>);
+
+impl<T: Get<(AssetId, u128, u128)>, R: TakeRevenue, M: Get<RoundingMode>>
+	FixedRateOfFungible<T, R, M>
+{
+	fn amount(weight: Weight, rounding: RoundingMode) -> u128 {
+		let (_, units_per_second, units_per_mb) = T::get();
+		rounding.mul_div(
+			units_per_second,
+			weight.ref_time() as u128,
+			WEIGHT_REF_TIME_PER_SECOND as u128,
+		) + rounding.mul_div(
+			units_per_mb,
+			weight.proof_size() as u128,
+			WEIGHT_PROOF_SIZE_PER_MB as u128,
+		)
+	}
+}
+
+impl<T: Get<(AssetId, u128, u128)>, R: TakeRevenue, M: Get<RoundingMode>> WeightTrader
+	for FixedRateOfFungible<T, R, M>
+{
 	fn new() -> Self {
 		Self(Weight::zero(), 0, PhantomData)
 	}
 
 	fn buy_weight(&mut self, weight: Weight, payment: Assets) -> Result<Assets, XcmError> {
+		self.buy_weight_detailed(weight, payment).map(|(unused, _)| unused)
+	}
+
+	fn buy_weight_detailed(
+		&mut self,
+		weight: Weight,
+		payment: Assets,
+	) -> Result<(Assets, Option<MultiAsset>), XcmError> {
 		log::trace!(
 			target: "xcm::weight",
 			"FixedRateOfFungible::buy_weight weight: {:?}, payment: {:?}",
 			weight, payment,
 		);
-		let (id, units_per_second, units_per_mb) = T::get();
-		let amount = (units_per_second * (weight.ref_time() as u128) /
-			(WEIGHT_REF_TIME_PER_SECOND as u128)) +
-			(units_per_mb * (weight.proof_size() as u128) / (WEIGHT_PROOF_SIZE_PER_MB as u128));
+		let id = T::get().0;
+		let amount = Self::amount(weight, RoundingMode::Down);
 		if amount == 0 {
-			return Ok(payment)
+			return Ok((payment, None))
 		}
-		let unused =
-			payment.checked_sub((id, amount).into()).map_err(|_| XcmError::TooExpensive)?;
+		let charged: MultiAsset = (id, amount).into();
+		let unused = payment.checked_sub(charged.clone()).map_err(|_| XcmError::TooExpensive)?;
 		self.0 = self.0.saturating_add(weight);
 		self.1 = self.1.saturating_add(amount);
-		Ok(unused)
+		Ok((unused, Some(charged)))
+	}
+
+	fn buy_partial_weight(
+		&mut self,
+		weight: Weight,
+		payment: Assets,
+	) -> Result<(Weight, Assets), XcmError> {
+		log::trace!(
+			target: "xcm::weight",
+			"FixedRateOfFungible::buy_partial_weight weight: {:?}, payment: {:?}",
+			weight, payment,
+		);
+		let id = T::get().0;
+		let amount = Self::amount(weight, RoundingMode::Down);
+		let available = payment.fungible.get(&id).copied().unwrap_or_default();
+		// only buy the share of the weight that can be paid for with the available amount.
+		let weight = if amount > available {
+			let scale = |total: u128| {
+				let ratio = Perquintill::from_rational(available, total);
+				Weight::from_parts(
+					ratio.mul_floor(weight.ref_time()),
+					ratio.mul_floor(weight.proof_size()),
+				)
+			};
+			// `amount` rounds both dimensions down, so the exact price of `weight` can be almost
+			// two units higher and the scaled weight may still cost more than `available`. Scaling
+			// against that upper bound instead always gives a weight that can be paid for.
+			let scaled = scale(amount);
+			if Self::amount(scaled, RoundingMode::Down) <= available {
+				scaled
+			} else {
+				scale(amount.saturating_add(2))
+			}
+		} else {
+			weight
+		};
+		self.buy_weight(weight, payment).map(|unused| (weight, unused))
 	}
 
 	fn refund_weight(&mut self, weight: Weight) -> Option<MultiAsset> {
 		log::trace!(target: "xcm::weight", "FixedRateOfFungible::refund_weight weight: {:?}", weight);
-		let (id, units_per_second, units_per_mb) = T::get();
+		let id = T::get().0;
 		let weight = weight.min(self.0);
-		let amount = (units_per_second * (weight.ref_time() as u128) /
-			(WEIGHT_REF_TIME_PER_SECOND as u128)) +
-			(units_per_mb * (weight.proof_size() as u128) / (WEIGHT_PROOF_SIZE_PER_MB as u128));
+		let amount = Self::amount(weight, M::get()).min(self.1);
 		self.0 -= weight;
 		self.1 = self.1.saturating_sub(amount);
 		if amount > 0 {
@@ -177,7 +320,9 @@ impl<T: Get<(AssetId, u128, u128)>, R: TakeRevenue> WeightTrader for FixedRateOf
 	}
 }
 
-impl<T: Get<(AssetId, u128, u128)>, R: TakeRevenue> Drop for FixedRateOfFungible<T, R> {
+impl<T: Get<(AssetId, u128, u128)>, R: TakeRevenue, M: Get<RoundingMode>> Drop
+	for FixedRateOfFungible<T, R, M>
+{
 	fn drop(&mut self) {
 		if self.1 > 0 {
 			R::take_revenue((T::get().0, self.1).into());
@@ -211,14 +356,22 @@ impl<
 	}
 
 	fn buy_weight(&mut self, weight: Weight, payment: Assets) -> Result<Assets, XcmError> {
+		self.buy_weight_detailed(weight, payment).map(|(unused, _)| unused)
+	}
+
+	fn buy_weight_detailed(
+		&mut self,
+		weight: Weight,
+		payment: Assets,
+	) -> Result<(Assets, Option<MultiAsset>), XcmError> {
 		log::trace!(target: "xcm::weight", "UsingComponents::buy_weight weight: {:?}, payment: {:?}", weight, payment);
 		let amount = WeightToFee::weight_to_fee(&weight);
 		let u128_amount: u128 = amount.try_into().map_err(|_| XcmError::Overflow)?;
-		let required = (Concrete(AssetId::get()), u128_amount).into();
-		let unused = payment.checked_sub(required).map_err(|_| XcmError::TooExpensive)?;
+		let required: MultiAsset = (Concrete(AssetId::get()), u128_amount).into();
+		let unused = payment.checked_sub(required.clone()).map_err(|_| XcmError::TooExpensive)?;
 		self.0 = self.0.saturating_add(weight);
 		self.1 = self.1.saturating_add(amount);
-		Ok(unused)
+		Ok((unused, (u128_amount > 0).then_some(required)))
 	}
 
 	fn refund_weight(&mut self, weight: Weight) -> Option<MultiAsset> {
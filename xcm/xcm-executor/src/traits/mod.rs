@@ -44,7 +44,7 @@ pub use should_execute::{CheckSuspension, ShouldExecute};
 mod transact_asset;
 pub use transact_asset::TransactAsset;
 mod weight;
-pub use weight::{WeightBounds, WeightTrader};
+pub use weight::{CappedWeightBounds, UniversalWeigher, WeightBounds, WeightTrader};
 
 pub mod prelude {
 	pub use super::{
@@ -15,7 +15,8 @@
 // along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
 
 use crate::Assets;
-use sp_std::result::Result;
+use frame_support::traits::Get;
+use sp_std::{marker::PhantomData, result::Result};
 use xcm::latest::{prelude::*, Weight};
 
 /// Determine the weight of an XCM message.
@@ -29,6 +30,68 @@ pub trait WeightBounds<RuntimeCall> {
 	fn instr_weight(instruction: &Instruction<RuntimeCall>) -> Result<Weight, ()>;
 }
 
+/// Wrapper around a `WeightBounds` which rejects messages with an absurd weight estimate.
+///
+/// Weighing fails if any instruction of the message, including the ones nested in
+/// `SetErrorHandler` and `SetAppendix`, weighs more than `MaxInstr`, or if the whole message weighs
+/// more than `MaxMessage`. This protects the executor against a faulty `Inner`, e.g. one based on a
+/// buggy `XcmWeightInfo`.
+///
+/// The message is weighed in a single pass from the weights of its instructions, as returned by
+/// `Inner::instr_weight`. `Inner::weight` is not used, so limits it applies on top, like a maximum
+/// number of instructions, have to be enforced elsewhere.
+pub struct CappedWeightBounds<Inner, MaxInstr, MaxMessage>(
+	PhantomData<(Inner, MaxInstr, MaxMessage)>,
+);
+impl<RuntimeCall, Inner, MaxInstr, MaxMessage> WeightBounds<RuntimeCall>
+	for CappedWeightBounds<Inner, MaxInstr, MaxMessage>
+where
+	Inner: WeightBounds<RuntimeCall>,
+	MaxInstr: Get<Weight>,
+	MaxMessage: Get<Weight>,
+{
+	fn weight(message: &mut Xcm<RuntimeCall>) -> Result<Weight, ()> {
+		let weight = Self::instrs_weight(message)?;
+		if weight.any_gt(MaxMessage::get()) {
+			log::trace!(target: "xcm::weight", "CappedWeightBounds message too heavy: {:?}", weight);
+			return Err(())
+		}
+		Ok(weight)
+	}
+
+	fn instr_weight(instruction: &Instruction<RuntimeCall>) -> Result<Weight, ()> {
+		// the nested instructions are weighed and capped on their own.
+		let (weight, nested_weight) = match instruction {
+			SetErrorHandler(xcm) =>
+				(Inner::instr_weight(&SetErrorHandler(Xcm::new()))?, Self::instrs_weight(xcm)?),
+			SetAppendix(xcm) =>
+				(Inner::instr_weight(&SetAppendix(Xcm::new()))?, Self::instrs_weight(xcm)?),
+			_ => (Inner::instr_weight(instruction)?, Weight::zero()),
+		};
+		if weight.any_gt(MaxInstr::get()) {
+			log::trace!(target: "xcm::weight", "CappedWeightBounds instruction too heavy: {:?}", weight);
+			return Err(())
+		}
+		weight.checked_add(&nested_weight).ok_or(())
+	}
+}
+
+impl<Inner, MaxInstr, MaxMessage> CappedWeightBounds<Inner, MaxInstr, MaxMessage>
+where
+	MaxInstr: Get<Weight>,
+	MaxMessage: Get<Weight>,
+{
+	fn instrs_weight<RuntimeCall>(message: &Xcm<RuntimeCall>) -> Result<Weight, ()>
+	where
+		Inner: WeightBounds<RuntimeCall>,
+	{
+		message.0.iter().try_fold(Weight::zero(), |acc, instruction| {
+			acc.checked_add(&<Self as WeightBounds<RuntimeCall>>::instr_weight(instruction)?)
+				.ok_or(())
+		})
+	}
+}
+
 /// A means of getting approximate weight consumption for a given destination message executor and a
 /// message.
 pub trait UniversalWeigher {
@@ -42,6 +105,10 @@ pub trait UniversalWeigher {
 /// `buy_weight` and `refund_weight` would be to attempt to call each tuple element's own
 /// implementation of these two functions, in the order of which they appear in the tuple,
 /// returning early when a successful result is returned.
+///
+/// `buy_partial_weight` of a tuple instead lets every element buy as much of the weight that is
+/// still left as it can, so that the weight can be paid for with several assets. Likewise,
+/// `refund_weight_multi` of a tuple collects the refunds of all the elements.
 pub trait WeightTrader: Sized {
 	/// Create a new trader instance.
 	fn new() -> Self;
@@ -51,6 +118,30 @@ pub trait WeightTrader: Sized {
 	/// for the `weight`, then an error is returned.
 	fn buy_weight(&mut self, weight: Weight, payment: Assets) -> Result<Assets, XcmError>;
 
+	/// Purchase execution weight credit like `buy_weight`, but also return the asset that was
+	/// charged for it, if known.
+	///
+	/// Default implementation defers to `buy_weight` and doesn't report the charged asset.
+	fn buy_weight_detailed(
+		&mut self,
+		weight: Weight,
+		payment: Assets,
+	) -> Result<(Assets, Option<MultiAsset>), XcmError> {
+		self.buy_weight(weight, payment).map(|unused| (unused, None))
+	}
+
+	/// Purchase as much of the given `weight` as the `payment` allows. Returns the weight that was
+	/// bought along with the unused part of the payment.
+	///
+	/// Default implementation is all-or-nothing and defers to `buy_weight`.
+	fn buy_partial_weight(
+		&mut self,
+		weight: Weight,
+		payment: Assets,
+	) -> Result<(Weight, Assets), XcmError> {
+		self.buy_weight(weight, payment).map(|unused| (weight, unused))
+	}
+
 	/// Attempt a refund of `weight` into some asset. The caller does not guarantee that the weight was
 	/// purchased using `buy_weight`.
 	///
@@ -58,6 +149,14 @@ pub trait WeightTrader: Sized {
 	fn refund_weight(&mut self, _weight: Weight) -> Option<MultiAsset> {
 		None
 	}
+
+	/// Attempt a refund of `weight` into any number of assets. The caller does not guarantee that
+	/// the weight was purchased using `buy_weight`.
+	///
+	/// Default implementation wraps `refund_weight`.
+	fn refund_weight_multi(&mut self, weight: Weight) -> Assets {
+		self.refund_weight(weight).map(Into::into).unwrap_or_default()
+	}
 }
 
 #[impl_trait_for_tuples::impl_for_tuples(30)]
@@ -67,11 +166,19 @@ impl WeightTrader for Tuple {
 	}
 
 	fn buy_weight(&mut self, weight: Weight, payment: Assets) -> Result<Assets, XcmError> {
+		self.buy_weight_detailed(weight, payment).map(|(unused, _)| unused)
+	}
+
+	fn buy_weight_detailed(
+		&mut self,
+		weight: Weight,
+		payment: Assets,
+	) -> Result<(Assets, Option<MultiAsset>), XcmError> {
 		let mut too_expensive_error_found = false;
 		let mut last_error = None;
 		for_tuples!( #(
-			match Tuple.buy_weight(weight, payment.clone()) {
-				Ok(assets) => return Ok(assets),
+			match Tuple.buy_weight_detailed(weight, payment.clone()) {
+				Ok(bought) => return Ok(bought),
 				Err(e) => {
 					if let XcmError::TooExpensive = e {
 						too_expensive_error_found = true;
@@ -92,6 +199,27 @@ impl WeightTrader for Tuple {
 		})
 	}
 
+	fn buy_partial_weight(
+		&mut self,
+		weight: Weight,
+		payment: Assets,
+	) -> Result<(Weight, Assets), XcmError> {
+		let mut remaining = weight;
+		let mut payment = payment;
+		for_tuples!( #(
+			if remaining.any_gt(Weight::zero()) {
+				if let Ok((bought, unused)) = Tuple.buy_partial_weight(remaining, payment.clone()) {
+					remaining = remaining.saturating_sub(bought);
+					payment = unused;
+				}
+			}
+		)* );
+
+		log::trace!(target: "xcm::buy_weight", "weight: {:?}, remaining: {:?}", weight, remaining);
+
+		Ok((weight.saturating_sub(remaining), payment))
+	}
+
 	fn refund_weight(&mut self, weight: Weight) -> Option<MultiAsset> {
 		for_tuples!( #(
 			if let Some(asset) = Tuple.refund_weight(weight) {
@@ -100,4 +228,88 @@ impl WeightTrader for Tuple {
 		)* );
 		None
 	}
+
+	fn refund_weight_multi(&mut self, weight: Weight) -> Assets {
+		let mut refunded = Assets::new();
+		for_tuples!( #(
+			refunded.subsume_assets(Tuple.refund_weight_multi(weight));
+		)* );
+		refunded
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	pub struct TransactWeigher;
+	impl WeightBounds<()> for TransactWeigher {
+		fn weight(message: &mut Xcm<()>) -> Result<Weight, ()> {
+			message
+				.0
+				.iter()
+				.try_fold(Weight::zero(), |acc, i| Ok(acc + Self::instr_weight(i)?))
+		}
+		fn instr_weight(instruction: &Instruction<()>) -> Result<Weight, ()> {
+			Ok(match instruction {
+				Transact { require_weight_at_most, .. } => *require_weight_at_most,
+				_ => Weight::from_parts(10, 10),
+			})
+		}
+	}
+
+	frame_support::parameter_types! {
+		pub const MaxInstrWeight: Weight = Weight::from_parts(100, 100);
+		pub const MaxMessageWeight: Weight = Weight::from_parts(250, 250);
+	}
+
+	type Capped = CappedWeightBounds<TransactWeigher, MaxInstrWeight, MaxMessageWeight>;
+
+	fn transact(ref_time: u64) -> Instruction<()> {
+		Transact {
+			origin_kind: OriginKind::Native,
+			require_weight_at_most: Weight::from_parts(ref_time, 0),
+			call: Vec::new().into(),
+		}
+	}
+
+	#[test]
+	fn capped_weight_bounds_rejects_heavy_instructions() {
+		let mut message = Xcm(vec![ClearOrigin, transact(101)]);
+		assert_eq!(Capped::instr_weight(&message.0[1]), Err(()));
+		assert_eq!(Capped::weight(&mut message), Err(()));
+
+		let mut message = Xcm(vec![ClearOrigin, transact(100), transact(90)]);
+		assert_eq!(Capped::weight(&mut message), Ok(Weight::from_parts(200, 10)));
+	}
+
+	#[test]
+	fn capped_weight_bounds_rejects_heavy_messages() {
+		let mut message = Xcm(vec![transact(100), transact(100), transact(60)]);
+		assert_eq!(Capped::weight(&mut message), Err(()));
+
+		let mut message = Xcm(vec![transact(100), transact(100), transact(50)]);
+		assert_eq!(Capped::weight(&mut message), Ok(Weight::from_parts(250, 0)));
+	}
+
+	#[test]
+	fn capped_weight_bounds_rejects_heavy_nested_instructions() {
+		let mut message = Xcm(vec![ClearOrigin, SetAppendix(Xcm(vec![transact(101)]))]);
+		assert_eq!(Capped::weight(&mut message), Err(()));
+
+		let mut message =
+			Xcm(vec![SetErrorHandler(Xcm(vec![SetAppendix(Xcm(vec![transact(101)]))]))]);
+		assert_eq!(Capped::weight(&mut message), Err(()));
+
+		// only the nested instructions are capped, not the instructions holding them.
+		let instruction = SetAppendix(Xcm(vec![transact(90), transact(90)]));
+		assert_eq!(Capped::instr_weight(&instruction), Ok(Weight::from_parts(190, 10)));
+		let mut message = Xcm(vec![ClearOrigin, instruction]);
+		assert_eq!(Capped::weight(&mut message), Ok(Weight::from_parts(200, 20)));
+
+		// the nested instructions count towards the weight of the message.
+		let mut message =
+			Xcm(vec![transact(100), SetAppendix(Xcm(vec![transact(100), transact(50)]))]);
+		assert_eq!(Capped::weight(&mut message), Err(()));
+	}
 }